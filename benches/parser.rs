@@ -0,0 +1,34 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use interpreter::lox::{parser::Parser, scanner::Scanner};
+
+// binary operations in the generated script
+const OPERATIONS: usize = 100_000;
+
+// five binary operations a line
+fn generate() -> String {
+    (0..OPERATIONS / 5).map(|i| format!("print a{} + b * c - d / e == {};\n", i, i)).collect()
+}
+
+// building the boxed tree and dropping it
+fn parse(c: &mut Criterion) {
+    let source = generate();
+    let mut scanner = Scanner::new(&source);
+    scanner.scan_tokens().unwrap();
+    let tokens = scanner.tokens;
+
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("build", |b| {
+        b.iter_batched(
+            || tokens.clone(),
+            |tokens| Parser::new(&source, tokens).parse(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("drop", |b| {
+        b.iter_batched(|| Parser::new(&source, tokens.clone()).parse(), drop, BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io::Cursor,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use interpreter::lox::scanner::Scanner;
+
+const SIZE: usize = 1 << 20;
+
+// counts allocations so the benches can show how many a scan makes, not just how long it takes
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// repeats `line` (with a counter substituted for `{}`) until the source reaches SIZE bytes
+fn generate(line: &str) -> String {
+    let mut source = String::with_capacity(SIZE + line.len() + 16);
+    let mut i = 0usize;
+    while source.len() < SIZE {
+        source.push_str(&line.replace("{}", &i.to_string()));
+        i += 1;
+    }
+    source
+}
+
+fn inputs() -> Vec<(&'static str, String)> {
+    vec![
+        ("expressions", generate("var v{} = (a * 3.25 - b_{} / 1_000) >= c.d(e, f) and !(g != {}) or h <= -i;\n")),
+        ("comments", generate("// a comment that goes on for a while, {} of many, with no tokens in it at all\n/* and a block */\n")),
+        ("strings", generate("print \"a fairly long string literal number {} with \\\"escapes\\\" and more text after them\";\n")),
+    ]
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    for (name, source) in inputs() {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("in_memory", name), &source, |b, source| {
+            b.iter(|| {
+                let mut scanner = Scanner::new(black_box(source));
+                let _ = black_box(scanner.scan_tokens());
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("streaming", name), &source, |b, source| {
+            b.iter(|| {
+                let mut tokens = 0;
+                Scanner::scan_reader(Cursor::new(black_box(source.as_bytes())), false, |scanner| tokens += scanner.tokens.len()).unwrap();
+                black_box(tokens)
+            })
+        });
+    }
+    group.finish();
+}
+
+// 100k uses of a handful of names: the interner allocates each name once
+fn identifiers(c: &mut Criterion) {
+    let names = ["i", "this", "count", "method_name", "other"];
+    let source = (0..100_000).map(|i| names[i % names.len()]).collect::<Vec<_>>().join(" ");
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let mut scanner = Scanner::new(&source);
+    let _ = scanner.scan_tokens();
+    eprintln!("identifiers: {} allocations for one scan", ALLOCATIONS.load(Ordering::Relaxed) - before);
+
+    c.bench_function("scan/identifiers", |b| {
+        b.iter(|| {
+            let mut scanner = Scanner::new(black_box(&source));
+            let _ = black_box(scanner.scan_tokens());
+        })
+    });
+}
+
+criterion_group!(benches, scan, identifiers);
+criterion_main!(benches);
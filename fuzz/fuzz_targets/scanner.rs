@@ -0,0 +1,31 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use interpreter::lox::{error::ScanError, scanner::Scanner};
+use libfuzzer_sys::fuzz_target;
+
+fn sorted(errors: &[ScanError]) -> Vec<(usize, usize, usize, String)> {
+    let mut errors = errors.iter().map(|e| (e.span.line, e.span.col, e.span.len, e.message.clone())).collect::<Vec<_>>();
+    errors.sort();
+    errors
+}
+
+// Any input scans without panicking (overflowing arithmetic included, fuzz builds check it) and ends
+// in an Eof token or in errors; the streaming path agrees with the in-memory one.
+fuzz_target!(|source: &str| {
+    let mut scanner = Scanner::new(source);
+    let errors = scanner.scan_tokens().err().unwrap_or_default();
+    let tokens = scanner.tokens.iter().map(|token| token.to_string()).collect::<Vec<_>>();
+    assert!(tokens.last().is_some_and(|eof| eof.starts_with("Eof ")) || !errors.is_empty());
+
+    let mut streamed_tokens = Vec::new();
+    let mut streamed_errors = Vec::new();
+    Scanner::scan_reader(Cursor::new(source.as_bytes()), false, |chunk| {
+        streamed_tokens.extend(chunk.tokens.iter().map(|token| token.to_string()));
+        streamed_errors.extend(chunk.errors.iter().cloned());
+    })
+    .unwrap();
+    assert_eq!(tokens, streamed_tokens);
+    assert_eq!(sorted(&errors), sorted(&streamed_errors));
+});
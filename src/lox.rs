@@ -1,54 +1,171 @@
 use anyhow::Result;
-use std::{io::Write, path::Path};
+use std::{fs, io::Write, path::Path};
 
-mod error;
-mod scanner;
-mod tokens;
+pub mod ast;
+pub mod callable;
+pub mod class;
+pub mod environment;
+pub mod error;
+pub mod interner;
+pub mod interpreter;
+pub mod natives;
+pub mod parser;
+pub mod scanner;
+pub mod tokens;
+pub mod value;
 
+use ast::printer::AstFormat;
+use error::{report_error, ScanError};
+use interpreter::Interpreter;
+use parser::Parser;
 use scanner::Scanner;
 
 pub struct Lox {
     pub has_error: bool,
+    // the script parsed but failed while running, exit code 70 rather than 65
+    pub has_runtime_error: bool,
+    // kept from one REPL line to the next
+    interpreter: Interpreter,
+    // print every scanned token, one per line (`--tokens`)
+    pub debug_tokens: bool,
+    // print the script rebuilt from its tokens, comments kept (`--render`)
+    pub render_tokens: bool,
+    // print every parsed statement as an s-expression or in RPN (`--print-ast[=lisp|rpn]`)
+    pub print_ast: Option<AstFormat>,
+    // follow every printed node with `@line:col` (`--spans`)
+    pub print_spans: bool,
+    // let `x / 0` be inf or NaN rather than a runtime error (`--ieee-division`)
+    pub ieee_division: bool,
+    // bytes of Rust stack Lox calls may take, see Interpreter::max_call_stack
+    pub max_call_stack: usize,
+    // stop after parsing and print nothing but diagnostics (`--parse`, `--check`)
+    pub check_only: bool,
+    // print the whole tree as JSON instead of running it (`--emit=ast-json`)
+    #[cfg(feature = "serde")]
+    pub emit_ast_json: bool,
 }
 
 impl Lox {
     pub fn new() -> Self {
-        Lox { has_error: false }
+        Lox {
+            has_error: false,
+            has_runtime_error: false,
+            interpreter: Interpreter::new(),
+            debug_tokens: false,
+            render_tokens: false,
+            print_ast: None,
+            print_spans: false,
+            ieee_division: false,
+            max_call_stack: interpreter::MAX_CALL_STACK,
+            check_only: false,
+            #[cfg(feature = "serde")]
+            emit_ast_json: false,
+        }
+    }
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Lox {
-    pub fn run_prompt() -> Result<i32> {
+    pub fn run_prompt(&mut self) -> Result<i32> {
         loop {
             print!("> ");
             std::io::stdout().flush().unwrap();
 
             let mut code = String::new();
 
-            std::io::stdin().read_line(&mut code)?;
+            // through the interpreter, which owns stdin so a script's readLine() takes the lines after it
+            if self.interpreter.read_line(&mut code)? == 0 {
+                return Ok(0);
+            }
 
-            match &code.lines().next().unwrap() {
-                x if x.len() == 0 => {
+            match code.lines().next().unwrap() {
+                "" => {
                     continue;
                 }
                 code => {
-                    let lox = Lox::new();
-                    lox.run(code);
+                    self.run_code(code, true);
+                    self.has_error = false;
+                    self.has_runtime_error = false;
                 }
             }
         }
     }
-    pub fn run_file(file: &Path) -> Result<i32> {
-        let code = std::fs::read_to_string(file)?;
+    pub fn run_file(&mut self, file: &Path) -> Result<i32> {
+        // the parser needs every token at once, so the script can't be scanned a chunk at a time
+        let code = fs::read_to_string(file)?;
 
-        let lox = Lox::new();
+        self.run(&code);
 
-        lox.run(&code);
+        if self.has_error {
+            return Ok(65);
+        }
+        if self.has_runtime_error {
+            return Ok(70);
+        }
 
         Ok(0)
     }
-    pub fn run(&self, code: &str) {
-        let mut scanner = Scanner::new([code, "\n"].concat());
-        println!("{:?}", scanner.scan_tokens());
+    pub fn run(&mut self, code: &str) {
+        self.run_code(code, false);
+    }
+    // `repl` lets a line be a lone expression, see Parser::parse_repl_line
+    fn run_code(&mut self, code: &str, repl: bool) {
+        let mut scanner = Scanner::new(code);
+        scanner.comments = self.render_tokens;
+        let errors = scanner.scan_tokens().err().unwrap_or_default();
+        self.show(&scanner, &errors);
+        // each phase only runs on input the previous ones accepted
+        if self.has_error {
+            return;
+        }
+
+        let mut parser = Parser::new(code, std::mem::take(&mut scanner.tokens));
+        let (statements, errors) = if repl { parser.parse_repl_line() } else { parser.parse() };
+        if !errors.is_empty() {
+            for error in errors {
+                report_error(error.span, scanner.line_text(error.span.line), error.message);
+            }
+            self.has_error = true;
+            return;
+        }
+        if self.check_only {
+            return;
+        }
+        #[cfg(feature = "serde")]
+        if self.emit_ast_json {
+            println!("{}", serde_json::to_string(&statements).unwrap());
+            return;
+        }
+        if let Some(format) = self.print_ast {
+            for statement in &statements {
+                println!("{}", ast::printer::print_stmt_as(statement, format, self.print_spans));
+            }
+        }
+        self.interpreter.ieee_division = self.ieee_division;
+        self.interpreter.max_call_stack = self.max_call_stack;
+        if let Err(error) = self.interpreter.interpret(&statements) {
+            report_error(error.span, scanner.line_text(error.span.line), error.message);
+            self.has_runtime_error = true;
+        }
+    }
+    fn show(&mut self, scanner: &Scanner, errors: &[ScanError]) {
+        // tokens around a lexical error are still scanned, so they're shown either way
+        if self.debug_tokens && !self.check_only {
+            for token in &scanner.tokens {
+                println!("{}", token);
+            }
+        }
+        if self.render_tokens && !self.check_only {
+            println!("{}", tokens::render(&scanner.tokens));
+        }
+        for error in errors {
+            report_error(error.span, scanner.line_text(error.span.line), error.message.clone());
+            self.has_error = true;
+        }
     }
 }
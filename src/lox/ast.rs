@@ -0,0 +1,301 @@
+use crate::lox::tokens::{Literal, Span, Token};
+
+pub mod printer;
+
+pub use printer::rpn;
+
+// `span` runs from the node's first token to its last one, so it can underline the whole node
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expr<'src> {
+    pub kind: ExprKind<'src>,
+    pub span: Span,
+}
+
+impl<'src> Expr<'src> {
+    pub fn new(kind: ExprKind<'src>, span: Span) -> Self {
+        Self { kind, span }
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    // the same tree with every token and literal owning its text, see Token::into_owned
+    pub fn into_owned(self) -> Expr<'static> {
+        let kind = match self.kind {
+            ExprKind::ArrayLiteral(elements) => ExprKind::ArrayLiteral(owned_exprs(elements)),
+            ExprKind::Assign { name, value } => ExprKind::Assign {
+                name: name.into_owned(),
+                value: owned_box(value),
+            },
+            ExprKind::Binary { left, operator, right } => ExprKind::Binary {
+                left: owned_box(left),
+                operator: operator.into_owned(),
+                right: owned_box(right),
+            },
+            ExprKind::Call { callee, paren, arguments } => ExprKind::Call {
+                callee: owned_box(callee),
+                paren: paren.into_owned(),
+                arguments: owned_exprs(arguments),
+            },
+            ExprKind::Get { object, name } => ExprKind::Get {
+                object: owned_box(object),
+                name: name.into_owned(),
+            },
+            ExprKind::Grouping(expr) => ExprKind::Grouping(owned_box(expr)),
+            ExprKind::Index { object, bracket, index } => ExprKind::Index {
+                object: owned_box(object),
+                bracket: bracket.into_owned(),
+                index: owned_box(index),
+            },
+            ExprKind::IndexSet { object, bracket, index, value } => ExprKind::IndexSet {
+                object: owned_box(object),
+                bracket: bracket.into_owned(),
+                index: owned_box(index),
+                value: owned_box(value),
+            },
+            ExprKind::Lambda { params, body } => ExprKind::Lambda {
+                params: owned_tokens(params),
+                body: owned_stmts(body),
+            },
+            ExprKind::Literal(literal) => ExprKind::Literal(literal.into_owned()),
+            ExprKind::Logical { left, operator, right } => ExprKind::Logical {
+                left: owned_box(left),
+                operator: operator.into_owned(),
+                right: owned_box(right),
+            },
+            ExprKind::Set { object, name, value } => ExprKind::Set {
+                object: owned_box(object),
+                name: name.into_owned(),
+                value: owned_box(value),
+            },
+            ExprKind::Super { keyword, method } => ExprKind::Super {
+                keyword: keyword.into_owned(),
+                method: method.into_owned(),
+            },
+            ExprKind::Ternary { condition, then_branch, else_branch } => ExprKind::Ternary {
+                condition: owned_box(condition),
+                then_branch: owned_box(then_branch),
+                else_branch: owned_box(else_branch),
+            },
+            ExprKind::This(keyword) => ExprKind::This(keyword.into_owned()),
+            ExprKind::Unary { operator, right } => ExprKind::Unary {
+                operator: operator.into_owned(),
+                right: owned_box(right),
+            },
+            ExprKind::Variable(name) => ExprKind::Variable(name.into_owned()),
+        };
+        Expr::new(kind, self.span)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExprKind<'src> {
+    ArrayLiteral(Vec<Expr<'src>>),
+    Assign {
+        name: Token<'src>,
+        value: Box<Expr<'src>>,
+    },
+    Binary {
+        left: Box<Expr<'src>>,
+        operator: Token<'src>,
+        right: Box<Expr<'src>>,
+    },
+    // `paren` is the closing one
+    Call {
+        callee: Box<Expr<'src>>,
+        paren: Token<'src>,
+        arguments: Vec<Expr<'src>>,
+    },
+    Get {
+        object: Box<Expr<'src>>,
+        name: Token<'src>,
+    },
+    Grouping(Box<Expr<'src>>),
+    // `bracket` is the closing one
+    Index {
+        object: Box<Expr<'src>>,
+        bracket: Token<'src>,
+        index: Box<Expr<'src>>,
+    },
+    IndexSet {
+        object: Box<Expr<'src>>,
+        bracket: Token<'src>,
+        index: Box<Expr<'src>>,
+        value: Box<Expr<'src>>,
+    },
+    // an anonymous `fun (params) { body }`
+    Lambda {
+        params: Vec<Token<'src>>,
+        body: Vec<Stmt<'src>>,
+    },
+    Literal(Literal<'src>),
+    // `and` and `or`, apart from Binary because they short-circuit
+    Logical {
+        left: Box<Expr<'src>>,
+        operator: Token<'src>,
+        right: Box<Expr<'src>>,
+    },
+    Set {
+        object: Box<Expr<'src>>,
+        name: Token<'src>,
+        value: Box<Expr<'src>>,
+    },
+    Super {
+        keyword: Token<'src>,
+        method: Token<'src>,
+    },
+    Ternary {
+        condition: Box<Expr<'src>>,
+        then_branch: Box<Expr<'src>>,
+        else_branch: Box<Expr<'src>>,
+    },
+    // the token is kept so a `this` outside any class can be reported where it is
+    This(Token<'src>),
+    // the operator token is kept so runtime errors can point at it
+    Unary {
+        operator: Token<'src>,
+        right: Box<Expr<'src>>,
+    },
+    Variable(Token<'src>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stmt<'src> {
+    pub kind: StmtKind<'src>,
+    pub span: Span,
+}
+
+impl<'src> Stmt<'src> {
+    pub fn new(kind: StmtKind<'src>, span: Span) -> Self {
+        Self { kind, span }
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn into_owned(self) -> Stmt<'static> {
+        let kind = match self.kind {
+            StmtKind::Block(statements) => StmtKind::Block(owned_stmts(statements)),
+            StmtKind::Break(keyword) => StmtKind::Break(keyword.into_owned()),
+            StmtKind::Print(expr) => StmtKind::Print(expr.into_owned()),
+            StmtKind::Return { keyword, value } => StmtKind::Return {
+                keyword: keyword.into_owned(),
+                value: value.map(Expr::into_owned),
+            },
+            StmtKind::Continue(keyword) => StmtKind::Continue(keyword.into_owned()),
+            StmtKind::Expression { expr, echo } => StmtKind::Expression {
+                expr: expr.into_owned(),
+                echo,
+            },
+            StmtKind::Class { name, superclass, methods } => StmtKind::Class {
+                name: name.into_owned(),
+                superclass: superclass.map(Expr::into_owned),
+                methods: methods.into_iter().map(Function::into_owned).collect(),
+            },
+            StmtKind::Function(function) => StmtKind::Function(function.into_owned()),
+            StmtKind::If { condition, then_branch, else_branch } => StmtKind::If {
+                condition: condition.into_owned(),
+                then_branch: Box::new(then_branch.into_owned()),
+                else_branch: else_branch.map(|branch| Box::new(branch.into_owned())),
+            },
+            StmtKind::Switch { scrutinee, arms } => StmtKind::Switch {
+                scrutinee: scrutinee.into_owned(),
+                arms: arms.into_iter().map(|(value, body)| (value.map(Expr::into_owned), owned_stmts(body))).collect(),
+            },
+            StmtKind::Var { name, initializer } => StmtKind::Var {
+                name: name.into_owned(),
+                initializer: initializer.map(Expr::into_owned),
+            },
+            StmtKind::While { condition, body, increment } => StmtKind::While {
+                condition: condition.into_owned(),
+                body: Box::new(body.into_owned()),
+                increment: increment.map(Expr::into_owned),
+            },
+        };
+        Stmt::new(kind, self.span)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StmtKind<'src> {
+    Block(Vec<Stmt<'src>>),
+    Break(Token<'src>),
+    Print(Expr<'src>),
+    Return {
+        keyword: Token<'src>,
+        value: Option<Expr<'src>>,
+    },
+    Continue(Token<'src>),
+    // `echo` for an expression typed at the prompt without a ';', whose value the REPL prints
+    Expression {
+        expr: Expr<'src>,
+        echo: bool,
+    },
+    Class {
+        name: Token<'src>,
+        // an ExprKind::Variable naming the parent class
+        superclass: Option<Expr<'src>>,
+        methods: Vec<Function<'src>>,
+    },
+    Function(Function<'src>),
+    If {
+        condition: Expr<'src>,
+        then_branch: Box<Stmt<'src>>,
+        else_branch: Option<Box<Stmt<'src>>>,
+    },
+    // an arm's value is None for `default`; arms don't fall through into the next one
+    Switch {
+        scrutinee: Expr<'src>,
+        arms: Vec<(Option<Expr<'src>>, Vec<Stmt<'src>>)>,
+    },
+    Var {
+        name: Token<'src>,
+        initializer: Option<Expr<'src>>,
+    },
+    While {
+        condition: Expr<'src>,
+        body: Box<Stmt<'src>>,
+        // a desugared for loop's increment, run after the body even when it `continue`s
+        increment: Option<Expr<'src>>,
+    },
+}
+
+// a `fun` declaration or a class method
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Function<'src> {
+    pub name: Token<'src>,
+    pub params: Vec<Token<'src>>,
+    pub body: Vec<Stmt<'src>>,
+    // from the name to the closing '}', without the `fun` of a declaration
+    pub span: Span,
+}
+
+impl Function<'_> {
+    pub fn into_owned(self) -> Function<'static> {
+        Function {
+            name: self.name.into_owned(),
+            params: owned_tokens(self.params),
+            body: owned_stmts(self.body),
+            span: self.span,
+        }
+    }
+}
+
+fn owned_box(expr: Box<Expr>) -> Box<Expr<'static>> {
+    Box::new(expr.into_owned())
+}
+
+fn owned_exprs(exprs: Vec<Expr>) -> Vec<Expr<'static>> {
+    exprs.into_iter().map(Expr::into_owned).collect()
+}
+
+fn owned_stmts(statements: Vec<Stmt>) -> Vec<Stmt<'static>> {
+    statements.into_iter().map(Stmt::into_owned).collect()
+}
+
+fn owned_tokens(tokens: Vec<Token>) -> Vec<Token<'static>> {
+    tokens.into_iter().map(Token::into_owned).collect()
+}
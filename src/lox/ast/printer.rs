@@ -0,0 +1,302 @@
+use crate::lox::ast::{Expr, ExprKind, Function, Stmt, StmtKind};
+use crate::lox::tokens::{Literal, Span, TokenType};
+
+// what `--print-ast` prints statements as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AstFormat {
+    Lisp,
+    Rpn,
+}
+
+// Lisp-style rendering for debugging the parser, `-123 * (45.67)` is `(* (- 123) (group 45.67))`
+pub fn print(expr: &Expr) -> String {
+    lisp(expr, false)
+}
+
+pub fn print_stmt(stmt: &Stmt) -> String {
+    lisp_stmt(stmt, false)
+}
+
+// Reverse Polish notation, `(1 + 2) * (4 - 3)` is `1 2 + 4 3 - *`: groupings disappear and unary minus is
+// `neg` so it can't be mistaken for a subtraction
+pub fn rpn(expr: &Expr) -> String {
+    postfix(expr, false)
+}
+
+pub fn rpn_stmt(stmt: &Stmt) -> String {
+    postfix_stmt(stmt, false)
+}
+
+// with `spans`, every node is followed by `@line:col` of where it starts
+pub fn print_stmt_as(stmt: &Stmt, format: AstFormat, spans: bool) -> String {
+    match format {
+        AstFormat::Lisp => lisp_stmt(stmt, spans),
+        AstFormat::Rpn => postfix_stmt(stmt, spans),
+    }
+}
+
+fn lisp(expr: &Expr, spans: bool) -> String {
+    let out = match &expr.kind {
+        ExprKind::ArrayLiteral(elements) => {
+            let mut out = String::from("(array");
+            for element in elements {
+                out.push(' ');
+                out.push_str(&lisp(element, spans));
+            }
+            out.push(')');
+            out
+        }
+        ExprKind::Assign { name, value } => format!("(= {} {})", name.lexeme(), lisp(value, spans)),
+        ExprKind::Binary { left, operator, right } => parenthesize(operator.lexeme(), &[left, right], spans),
+        ExprKind::Call { callee, arguments, .. } => {
+            let mut out = format!("(call {}", lisp(callee, spans));
+            for argument in arguments {
+                out.push(' ');
+                out.push_str(&lisp(argument, spans));
+            }
+            out.push(')');
+            out
+        }
+        ExprKind::Get { object, name } => format!("(. {} {})", lisp(object, spans), name.lexeme()),
+        ExprKind::Grouping(expr) => parenthesize("group", &[expr], spans),
+        ExprKind::Index { object, index, .. } => parenthesize("index", &[object, index], spans),
+        ExprKind::IndexSet { object, index, value, .. } => {
+            format!("(= {} {})", parenthesize("index", &[object, index], spans), lisp(value, spans))
+        }
+        ExprKind::Lambda { params, body } => {
+            let params: Vec<&str> = params.iter().map(|param| param.lexeme()).collect();
+            format!("(fun ({}) {})", params.join(" "), print_block(body, spans))
+        }
+        ExprKind::Literal(literal) => print_literal(literal),
+        ExprKind::Logical { left, operator, right } => parenthesize(operator.lexeme(), &[left, right], spans),
+        ExprKind::Set { object, name, value } => format!("(= (. {} {}) {})", lisp(object, spans), name.lexeme(), lisp(value, spans)),
+        ExprKind::Ternary { condition, then_branch, else_branch } => {
+            parenthesize("?:", &[condition, then_branch, else_branch], spans)
+        }
+        ExprKind::Unary { operator, right } => parenthesize(operator.lexeme(), &[right], spans),
+        ExprKind::Super { method, .. } => format!("(super {})", method.lexeme()),
+        ExprKind::This(_) => "this".to_string(),
+        ExprKind::Variable(name) => name.lexeme().to_string(),
+    };
+    at(out, expr.span, spans)
+}
+
+fn lisp_stmt(stmt: &Stmt, spans: bool) -> String {
+    let out = match &stmt.kind {
+        StmtKind::Block(statements) => print_block(statements, spans),
+        StmtKind::Break(_) => "(break)".to_string(),
+        StmtKind::Continue(_) => "(continue)".to_string(),
+        StmtKind::Print(expr) => parenthesize("print", &[expr], spans),
+        StmtKind::Return { value: Some(value), .. } => parenthesize("return", &[value], spans),
+        StmtKind::Return { value: None, .. } => "(return)".to_string(),
+        StmtKind::Expression { expr, echo: false } => parenthesize("expr", &[expr], spans),
+        StmtKind::Expression { expr, echo: true } => parenthesize("echo", &[expr], spans),
+        StmtKind::Class { name, superclass, methods } => {
+            let mut out = format!("(class {}", name.lexeme());
+            if let Some(superclass) = superclass {
+                out.push_str(&format!(" < {}", lisp(superclass, spans)));
+            }
+            for method in methods {
+                out.push(' ');
+                out.push_str(&at(print_function(method, spans), method.span, spans));
+            }
+            out.push(')');
+            out
+        }
+        StmtKind::Function(function) => print_function(function, spans),
+        StmtKind::If { condition, then_branch, else_branch: Some(else_branch) } => {
+            format!("(if {} {} {})", lisp(condition, spans), lisp_stmt(then_branch, spans), lisp_stmt(else_branch, spans))
+        }
+        StmtKind::If { condition, then_branch, else_branch: None } => {
+            format!("(if {} {})", lisp(condition, spans), lisp_stmt(then_branch, spans))
+        }
+        StmtKind::Switch { scrutinee, arms } => {
+            let mut out = format!("(switch {}", lisp(scrutinee, spans));
+            for (value, body) in arms {
+                match value {
+                    Some(value) => out.push_str(&format!(" (case {}", lisp(value, spans))),
+                    None => out.push_str(" (default"),
+                }
+                for statement in body {
+                    out.push(' ');
+                    out.push_str(&lisp_stmt(statement, spans));
+                }
+                out.push(')');
+            }
+            out.push(')');
+            out
+        }
+        StmtKind::Var { name, initializer: Some(initializer) } => format!("(var {} {})", name.lexeme(), lisp(initializer, spans)),
+        StmtKind::Var { name, initializer: None } => format!("(var {})", name.lexeme()),
+        StmtKind::While { condition, body, increment: Some(increment) } => {
+            format!("(while {} {} {})", lisp(condition, spans), lisp_stmt(body, spans), lisp(increment, spans))
+        }
+        StmtKind::While { condition, body, increment: None } => format!("(while {} {})", lisp(condition, spans), lisp_stmt(body, spans)),
+    };
+    at(out, stmt.span, spans)
+}
+
+fn postfix(expr: &Expr, spans: bool) -> String {
+    let out = match &expr.kind {
+        ExprKind::ArrayLiteral(elements) => {
+            let mut out = String::new();
+            for element in elements {
+                out.push_str(&postfix(element, spans));
+                out.push(' ');
+            }
+            format!("{}{} array", out, elements.len())
+        }
+        ExprKind::Assign { name, value } => format!("{} {} =", name.lexeme(), postfix(value, spans)),
+        ExprKind::Binary { left, operator, right } => format!("{} {} {}", postfix(left, spans), postfix(right, spans), operator.lexeme()),
+        // like blocks, with the count of arguments
+        ExprKind::Call { callee, arguments, .. } => {
+            let mut out = format!("{} ", postfix(callee, spans));
+            for argument in arguments {
+                out.push_str(&postfix(argument, spans));
+                out.push(' ');
+            }
+            format!("{}{} call", out, arguments.len())
+        }
+        ExprKind::Get { object, name } => format!("{} {} .", postfix(object, spans), name.lexeme()),
+        ExprKind::Grouping(expr) => postfix(expr, spans),
+        ExprKind::Index { object, index, .. } => format!("{} {} index", postfix(object, spans), postfix(index, spans)),
+        ExprKind::IndexSet { object, index, value, .. } => {
+            format!("{} {} {} index=", postfix(object, spans), postfix(index, spans), postfix(value, spans))
+        }
+        ExprKind::Lambda { params, body } => {
+            let mut out = String::new();
+            for param in params {
+                out.push_str(param.lexeme());
+                out.push(' ');
+            }
+            format!("{}{} {} fun", out, params.len(), rpn_block(body, spans))
+        }
+        ExprKind::Literal(literal) => print_literal(literal),
+        ExprKind::Logical { left, operator, right } => format!("{} {} {}", postfix(left, spans), postfix(right, spans), operator.lexeme()),
+        ExprKind::Set { object, name, value } => format!("{} {} {} .=", postfix(object, spans), name.lexeme(), postfix(value, spans)),
+        ExprKind::Ternary { condition, then_branch, else_branch } => {
+            format!("{} {} {} ?:", postfix(condition, spans), postfix(then_branch, spans), postfix(else_branch, spans))
+        }
+        ExprKind::Unary { operator, right } if operator.ttype() == TokenType::Minus => format!("{} neg", postfix(right, spans)),
+        ExprKind::Unary { operator, right } => format!("{} {}", postfix(right, spans), operator.lexeme()),
+        ExprKind::Super { method, .. } => format!("{} super", method.lexeme()),
+        ExprKind::This(_) => "this".to_string(),
+        ExprKind::Variable(name) => name.lexeme().to_string(),
+    };
+    at(out, expr.span, spans)
+}
+
+fn postfix_stmt(stmt: &Stmt, spans: bool) -> String {
+    let out = match &stmt.kind {
+        StmtKind::Block(statements) => rpn_block(statements, spans),
+        StmtKind::Break(_) => "break".to_string(),
+        StmtKind::Continue(_) => "continue".to_string(),
+        StmtKind::Print(expr) => format!("{} print", postfix(expr, spans)),
+        StmtKind::Return { value: Some(value), .. } => format!("{} return", postfix(value, spans)),
+        StmtKind::Return { value: None, .. } => "return".to_string(),
+        StmtKind::Expression { expr, echo: false } => postfix(expr, spans),
+        StmtKind::Expression { expr, echo: true } => format!("{} echo", postfix(expr, spans)),
+        StmtKind::Class { name, superclass, methods } => {
+            let mut out = format!("{} ", name.lexeme());
+            if let Some(superclass) = superclass {
+                out.push_str(&format!("{} < ", postfix(superclass, spans)));
+            }
+            for method in methods {
+                out.push_str(&at(rpn_function(method, spans), method.span, spans));
+                out.push(' ');
+            }
+            format!("{}{} class", out, methods.len())
+        }
+        StmtKind::Function(function) => rpn_function(function, spans),
+        StmtKind::If { condition, then_branch, else_branch: Some(else_branch) } => {
+            format!("{} {} {} if-else", postfix(condition, spans), postfix_stmt(then_branch, spans), postfix_stmt(else_branch, spans))
+        }
+        StmtKind::If { condition, then_branch, else_branch: None } => {
+            format!("{} {} if", postfix(condition, spans), postfix_stmt(then_branch, spans))
+        }
+        // each arm is its value (if any) and statements with their count, then the count of arms
+        StmtKind::Switch { scrutinee, arms } => {
+            let mut out = format!("{} ", postfix(scrutinee, spans));
+            for (value, body) in arms {
+                if let Some(value) = value {
+                    out.push_str(&postfix(value, spans));
+                    out.push(' ');
+                }
+                for statement in body {
+                    out.push_str(&postfix_stmt(statement, spans));
+                    out.push(' ');
+                }
+                let label = if value.is_some() { "case" } else { "default" };
+                out.push_str(&format!("{} {} ", body.len(), label));
+            }
+            format!("{}{} switch", out, arms.len())
+        }
+        StmtKind::Var { name, initializer: Some(initializer) } => format!("{} {} var", name.lexeme(), postfix(initializer, spans)),
+        StmtKind::Var { name, initializer: None } => format!("{} var", name.lexeme()),
+        StmtKind::While { condition, body, increment: Some(increment) } => {
+            format!("{} {} {} while-step", postfix(condition, spans), postfix_stmt(body, spans), postfix(increment, spans))
+        }
+        StmtKind::While { condition, body, increment: None } => format!("{} {} while", postfix(condition, spans), postfix_stmt(body, spans)),
+    };
+    at(out, stmt.span, spans)
+}
+
+fn print_function(function: &Function, spans: bool) -> String {
+    let params: Vec<&str> = function.params.iter().map(|param| param.lexeme()).collect();
+    format!("(fun {} ({}) {})", function.name.lexeme(), params.join(" "), print_block(&function.body, spans))
+}
+
+fn rpn_function(function: &Function, spans: bool) -> String {
+    let mut out = format!("{} ", function.name.lexeme());
+    for param in &function.params {
+        out.push_str(param.lexeme());
+        out.push(' ');
+    }
+    format!("{}{} {} fun", out, function.params.len(), rpn_block(&function.body, spans))
+}
+
+fn print_block(statements: &[Stmt], spans: bool) -> String {
+    let mut out = String::from("(block");
+    for statement in statements {
+        out.push(' ');
+        out.push_str(&lisp_stmt(statement, spans));
+    }
+    out.push(')');
+    out
+}
+
+// the count says how many statements the block takes off the stack
+fn rpn_block(statements: &[Stmt], spans: bool) -> String {
+    let mut out = String::new();
+    for statement in statements {
+        out.push_str(&postfix_stmt(statement, spans));
+        out.push(' ');
+    }
+    format!("{}{} block", out, statements.len())
+}
+
+fn parenthesize(name: &str, exprs: &[&Expr], spans: bool) -> String {
+    let mut out = format!("({}", name);
+    for expr in exprs {
+        out.push(' ');
+        out.push_str(&lisp(expr, spans));
+    }
+    out.push(')');
+    out
+}
+
+// strings are quoted so `"1"` and `1` print differently
+fn print_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Str(string) => format!("{:?}", string),
+        literal => literal.to_string(),
+    }
+}
+
+fn at(out: String, span: Span, spans: bool) -> String {
+    if spans {
+        format!("{}@{}:{}", out, span.line, span.col)
+    } else {
+        out
+    }
+}
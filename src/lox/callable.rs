@@ -0,0 +1,108 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::lox::ast::Function;
+use crate::lox::environment::Environment;
+use crate::lox::error::RuntimeError;
+use crate::lox::interpreter::{Flow, Interpreter};
+use crate::lox::tokens::Token;
+use crate::lox::value::Value;
+
+// Whatever a call expression can be applied to. The interpreter checks the argument count against `arity`
+// before calling, so `call` always gets exactly that many. `paren` is the call's closing one, where an error
+// the callee raises itself (rather than one from a statement it runs) points.
+pub trait Callable {
+    fn arity(&self) -> usize;
+    fn call(&self, interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Value>) -> Result<Value, RuntimeError>;
+}
+
+// A `fun` declaration at runtime. The declaration owns its text (see Interpreter::function) and the closure
+// is the scope it was declared in, so it sees the variables around it even once that scope is gone.
+pub struct LoxFunction {
+    declaration: Rc<Function<'static>>,
+    closure: Rc<RefCell<Environment>>,
+}
+
+impl LoxFunction {
+    pub fn new(declaration: Rc<Function<'static>>, closure: Rc<RefCell<Environment>>) -> Self {
+        Self { declaration, closure }
+    }
+    pub fn name(&self) -> &str {
+        self.declaration.name.lexeme()
+    }
+    // The method as read off `instance`: a copy whose closure has `this` defined as the instance, so it still
+    // works once stored elsewhere (`var m = obj.method; m();`).
+    pub fn bind(&self, instance: Value) -> Self {
+        let mut scope = Environment::with_enclosing(Rc::clone(&self.closure));
+        scope.define("this", instance);
+        Self::new(Rc::clone(&self.declaration), Rc::new(RefCell::new(scope)))
+    }
+}
+
+impl Callable for LoxFunction {
+    fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+    // each call gets its own scope for the parameters, enclosed by the closure rather than by the caller's
+    fn call(&self, interpreter: &mut Interpreter, _paren: &Token, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let mut scope = Environment::with_enclosing(Rc::clone(&self.closure));
+        for (param, argument) in self.declaration.params.iter().zip(arguments) {
+            scope.define(param.lexeme(), argument);
+        }
+        match interpreter.execute_block(&self.declaration.body, scope)? {
+            Flow::Return(value) => Ok(value),
+            // falling off the end returns nil
+            _ => Ok(Value::Nil),
+        }
+    }
+}
+
+// the closure isn't printed, it can hold the function itself
+impl fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LoxFunction({})", self.name())
+    }
+}
+
+// a function is only equal to itself
+impl PartialEq for LoxFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// the Rust side of a native: gets exactly `arity` arguments, an error is the message for a runtime error at the call
+pub type NativeFn = fn(&mut Interpreter, Vec<Value>) -> Result<Value, String>;
+
+// A function built into the interpreter (see natives.rs), called like any Lox function.
+pub struct NativeFunction {
+    pub name: &'static str,
+    arity: usize,
+    function: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn new(name: &'static str, arity: usize, function: NativeFn) -> Self {
+        Self { name, arity, function }
+    }
+}
+
+impl Callable for NativeFunction {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+    fn call(&self, interpreter: &mut Interpreter, paren: &Token, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        (self.function)(interpreter, arguments).map_err(|message| RuntimeError::new(paren.span(), &message))
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeFunction({})", self.name)
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
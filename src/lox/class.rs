@@ -0,0 +1,92 @@
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+use crate::lox::callable::{Callable, LoxFunction};
+use crate::lox::error::RuntimeError;
+use crate::lox::interpreter::Interpreter;
+use crate::lox::tokens::Token;
+use crate::lox::value::Value;
+
+// A `class` declaration at runtime, called like a function to make an instance. Its methods are unbound,
+// they get a `this` when read off an instance.
+pub struct LoxClass {
+    pub name: String,
+    methods: HashMap<String, Rc<LoxFunction>>,
+}
+
+impl LoxClass {
+    pub fn new(name: &str, methods: HashMap<String, Rc<LoxFunction>>) -> Self {
+        Self {
+            name: name.to_string(),
+            methods,
+        }
+    }
+    pub fn find_method(&self, name: &str) -> Option<&Rc<LoxFunction>> {
+        self.methods.get(name)
+    }
+}
+
+// on the Rc since every instance keeps a handle to its class
+impl Callable for Rc<LoxClass> {
+    fn arity(&self) -> usize {
+        0
+    }
+    fn call(&self, _: &mut Interpreter, _: &Token, _: Vec<Value>) -> Result<Value, RuntimeError> {
+        Ok(Value::Instance(Rc::new(RefCell::new(LoxInstance::new(Rc::clone(self))))))
+    }
+}
+
+impl fmt::Debug for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LoxClass({})", self.name)
+    }
+}
+
+// a class is only equal to itself
+impl PartialEq for LoxClass {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// An object made by calling a class. Fields aren't declared anywhere, setting one creates it.
+pub struct LoxInstance {
+    pub class: Rc<LoxClass>,
+    fields: HashMap<String, Value>,
+}
+
+impl LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+    // A field, or else a method bound to `instance` (fields shadow methods). `name` is the property's token,
+    // where reading one the instance doesn't have is reported.
+    pub fn get(instance: &Rc<RefCell<Self>>, name: &Token) -> Result<Value, RuntimeError> {
+        let this = instance.borrow();
+        if let Some(value) = this.fields.get(name.lexeme()) {
+            return Ok(value.clone());
+        }
+        match this.class.find_method(name.lexeme()) {
+            Some(method) => Ok(Value::Function(Rc::new(method.bind(Value::Instance(Rc::clone(instance)))))),
+            None => Err(RuntimeError::new(name.span(), &format!("Undefined property '{}'", name.lexeme()))),
+        }
+    }
+    pub fn set(&mut self, name: &Token, value: Value) {
+        self.fields.insert(name.lexeme().to_string(), value);
+    }
+}
+
+// the fields aren't printed, one can hold the instance itself
+impl fmt::Debug for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LoxInstance({})", self.class.name)
+    }
+}
+
+impl PartialEq for LoxInstance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
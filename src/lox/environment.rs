@@ -0,0 +1,53 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::lox::error::RuntimeError;
+use crate::lox::tokens::Token;
+use crate::lox::value::Value;
+
+// Variables by name, for one scope. Lookups and assignments walk out through the enclosing scopes and take
+// the identifier token so an undefined name is reported where it's used.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    // None for the globals
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+    // always in this scope, shadowing any outer variable of the same name. `var` on a name this scope already
+    // has replaces it rather than failing, so a script (or REPL line) can be rerun
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.values.insert(name.to_string(), value);
+    }
+    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
+        match (self.values.get(name.lexeme()), &self.enclosing) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(enclosing)) => enclosing.borrow().get(name),
+            (None, None) => Err(undefined(name)),
+        }
+    }
+    // only an existing variable can be assigned, in the innermost scope that has it; assignment never creates one
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        match (self.values.get_mut(name.lexeme()), &self.enclosing) {
+            (Some(slot), _) => {
+                *slot = value;
+                Ok(())
+            }
+            (None, Some(enclosing)) => enclosing.borrow_mut().assign(name, value),
+            (None, None) => Err(undefined(name)),
+        }
+    }
+}
+
+fn undefined(name: &Token) -> RuntimeError {
+    RuntimeError::new(name.span(), &format!("Undefined variable '{}'", name.lexeme()))
+}
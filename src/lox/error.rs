@@ -1,25 +1,143 @@
 use colored::Colorize;
-use std::iter;
 
-pub fn report_error(line: usize, offset: usize, code: &String, message: String) {
+use crate::lox::tokens::Span;
+
+pub const TAB_WIDTH: usize = 4;
+
+// Underlines `span` (at least one character) in `code_line`, the text of span.line (see Scanner::line_text)
+pub fn report_error(span: Span, code_line: &str, message: String) {
+    eprintln!("{}", render_error(span, code_line, message, TAB_WIDTH));
+}
+
+pub fn render_error(span: Span, code_line: &str, message: String, tab_width: usize) -> String {
+    let Span { line, col: offset, len } = span;
     let (slice_back, slice_front) = (15usize, 15usize);
-    let line_pos = format!("[line: {}; pos: {}]", format!("{}", line).blue(), format!("{}", offset).blue());
-    println!("
+    let line_pos = format!("[line: {}; col: {}]", format!("{}", line).blue(), format!("{}", offset).blue());
+    // the stored offset doesn't change, only the excerpt and the padding under it are computed on expanded text
+    let (expanded_line, end) = expand_tabs(code_line, offset + len.max(1), tab_width);
+    let (code_line, offset) = expand_tabs(code_line, offset, tab_width);
+    let len = end - offset;
+    format!("
     {}
     {}
     {}{}{}
-    {}{}
-    {}{}
-    {}{}
+    {}^
+    {}|
+    {}+
     Error msg: {}",
 
-    format!("ERROR").red().bold(),
+    "ERROR".red().bold(),
     line_pos,
-    format!("{}", code.clone().lines().nth(line - 1).into_iter().collect::<String>().chars().skip(offset.max(slice_front) - slice_front).take(offset.min(slice_front)).collect::<String>()).yellow(),
-    format!("{}", code.clone().lines().nth(line - 1).into_iter().collect::<String>().chars().skip(offset.max(1)).take(1).collect::<String>()).red().underline(),
-    format!("{}", code.clone().lines().nth(line - 1).into_iter().collect::<String>().chars().skip(offset + 1).take(slice_back).collect::<String>()).yellow(),
-    iter::repeat(" ").take(offset.min(slice_front)).collect::<String>(), "^",
-    iter::repeat(" ").take(offset.min(slice_front)).collect::<String>(), "|",
-    iter::repeat("-").take(offset.min(slice_front)).collect::<String>(), "+",
-    format!("{}", message).red().underline());
+    code_line.chars().skip(offset.max(slice_front) - slice_front).take(offset.min(slice_front)).collect::<String>().yellow(),
+    expanded_line.chars().skip(offset).take(len).collect::<String>().red().underline(),
+    expanded_line.chars().skip(offset + len).take(slice_back).collect::<String>().yellow(),
+    " ".repeat(offset.min(slice_front)),
+    " ".repeat(offset.min(slice_front)),
+    "-".repeat(offset.min(slice_front)),
+    message.red().underline())
+}
+
+// Replaces tabs with spaces up to the next multiple of `tab_width` and maps `offset` onto the expanded line.
+fn expand_tabs(code_line: &str, offset: usize, tab_width: usize) -> (String, usize) {
+    let mut expanded = String::with_capacity(code_line.len());
+    let mut column = 0usize;
+    let mut expanded_offset = None;
+
+    for (i, c) in code_line.chars().enumerate() {
+        if i == offset {
+            expanded_offset = Some(column);
+        }
+        if c == '\t' {
+            let width = tab_width - column % tab_width;
+            expanded.push_str(&" ".repeat(width));
+            column += width;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+
+    let past_end = offset.saturating_sub(code_line.chars().count());
+    (expanded, expanded_offset.unwrap_or(column + past_end))
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl ScanError {
+    pub fn new(line: usize, col: usize, len: usize, message: String) -> Self {
+        Self {
+            span: Span::new(line, col, len),
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl ParseError {
+    // `span` is the offending token's
+    pub fn new(span: Span, message: String) -> Self {
+        Self { span, message }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl RuntimeError {
+    // `span` is the operator's, or the node's that can't be evaluated
+    pub fn new(span: Span, message: &str) -> Self {
+        Self {
+            span,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_error, TAB_WIDTH};
+    use crate::lox::{scanner::Scanner, tokens::Span};
+
+    // the character of the excerpt the caret under it points at (render without colors)
+    fn underlined(rendered: &str) -> char {
+        let lines: Vec<&str> = rendered.lines().collect();
+        let caret = lines.iter().position(|line| line.trim() == "^").expect(rendered);
+        let column = lines[caret].chars().count() - 1;
+        lines[caret - 1].chars().nth(column).expect(rendered)
+    }
+
+    #[test]
+    fn caret_points_at_the_error_after_an_emoji() {
+        colored::control::set_override(false);
+        let source = "print \"🎉\" @;";
+        let mut scanner = Scanner::new(source);
+        let errors = scanner.scan_tokens().unwrap_err();
+        assert_eq!(errors[0].span, Span::new(1, 10, 1));
+        let rendered = render_error(errors[0].span, scanner.line_text(1), errors[0].message.clone(), TAB_WIDTH);
+        assert_eq!(underlined(&rendered), '@');
+    }
+
+    #[test]
+    fn caret_lines_up_under_tabs() {
+        colored::control::set_override(false);
+        let rendered = render_error(Span::new(1, 2, 3), "\t\tfoo = 1;", "msg".to_string(), TAB_WIDTH);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[3], "            foo = 1;");
+        assert_eq!(lines[4], "            ^");
+        assert_eq!(lines[5], "            |");
+        assert_eq!(lines[6], "    --------+");
+        assert_eq!(underlined(&rendered), 'f');
+    }
 }
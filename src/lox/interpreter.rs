@@ -0,0 +1,344 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::lox::ast::{Expr, ExprKind, Function, Stmt, StmtKind};
+use crate::lox::callable::{Callable, LoxFunction};
+use crate::lox::class::{LoxClass, LoxInstance};
+use crate::lox::environment::Environment;
+use crate::lox::error::RuntimeError;
+use crate::lox::natives::define_natives;
+use crate::lox::tokens::{Literal, Token, TokenType};
+use crate::lox::value::Value;
+
+type RuntimeResult<T> = Result<T, RuntimeError>;
+
+// How much Rust stack Lox calls may take by default. A call is a few Rust frames, more when its body nests
+// deeply, so the limit is in bytes rather than calls; this much leaves room on a 2MB thread, the smallest
+// Rust spawns, in a debug build too.
+pub const MAX_CALL_STACK: usize = 1024 * 1024;
+
+// How a statement finished: `break` and `continue` unwind out through blocks and ifs to the closest loop,
+// `return` out through loops too, up to the call (the parser rejects each of them anywhere else).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+// Tree-walking evaluator, run on statements the parser accepted. A runtime error stops the run and points at
+// the operator or node that caused it.
+pub struct Interpreter {
+    // dividing by zero gives inf or NaN as in IEEE 754, instead of a runtime error
+    pub ieee_division: bool,
+    // where readLine() reads, stdin unless scripted input is fed in
+    input: Box<dyn BufRead>,
+    // where `print` writes, stdout unless the interpreter is embedded or its output captured
+    out: Box<dyn Write>,
+    // the innermost scope, swapped for a child while a block runs
+    environment: Rc<RefCell<Environment>>,
+    // Lox calls in progress, where the Rust stack was when the outermost one started, and how far past that
+    // it may grow: the limit has to fit the stack of the thread interpreting (see main.rs)
+    call_depth: usize,
+    stack_base: usize,
+    pub max_call_stack: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::with_output(Box::new(io::stdout()))
+    }
+    pub fn with_output(out: Box<dyn Write>) -> Self {
+        // locking stdin only for each read, so that another interpreter can still be made
+        Self::with_io(Box::new(io::BufReader::new(io::stdin())), out)
+    }
+    pub fn with_io(input: Box<dyn BufRead>, out: Box<dyn Write>) -> Self {
+        let mut globals = Environment::new();
+        define_natives(&mut globals);
+        Self {
+            ieee_division: false,
+            input,
+            out,
+            environment: Rc::new(RefCell::new(globals)),
+            call_depth: 0,
+            stack_base: 0,
+            max_call_stack: MAX_CALL_STACK,
+        }
+    }
+    pub fn interpret(&mut self, statements: &[Stmt]) -> RuntimeResult<()> {
+        for statement in statements {
+            self.execute(statement)?;
+        }
+        Ok(())
+    }
+    // Each kind of statement (and expression, in evaluate) with more than a line or two of work has its own
+    // method: these two recurse for every nested node and Lox call, so their frames are kept small.
+    fn execute(&mut self, stmt: &Stmt) -> RuntimeResult<Flow> {
+        match &stmt.kind {
+            // run for its effects (a call, an assignment) and dropped, unless it's a bare REPL expression whose
+            // value is shown; an error propagates and stops the statements after it
+            StmtKind::Expression { expr, echo: false } => self.evaluate(expr).map(|_| Flow::Normal),
+            StmtKind::Expression { expr, echo: true } => self.print(expr, stmt, Value::repr),
+            StmtKind::Print(expr) => self.print(expr, stmt, Value::to_string),
+            StmtKind::Block(statements) => {
+                let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+                self.execute_block(statements, scope)
+            }
+            StmtKind::If { condition, then_branch, else_branch } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.execute(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)
+                } else {
+                    Ok(Flow::Normal)
+                }
+            }
+            StmtKind::While { condition, body, increment } => self.while_loop(condition, body, increment.as_ref()),
+            StmtKind::Break(_) => Ok(Flow::Break),
+            StmtKind::Continue(_) => Ok(Flow::Continue),
+            StmtKind::Return { value: Some(value), .. } => Ok(Flow::Return(self.evaluate(value)?)),
+            StmtKind::Return { value: None, .. } => Ok(Flow::Return(Value::Nil)),
+            StmtKind::Function(declaration) => {
+                let function = Value::Function(Rc::new(self.function(declaration)));
+                self.environment.borrow_mut().define(declaration.name.lexeme(), function);
+                Ok(Flow::Normal)
+            }
+            StmtKind::Class { name, superclass, methods } => self.class(name, superclass.as_ref(), methods),
+            StmtKind::Var { name, initializer } => {
+                let value = match initializer {
+                    Some(initializer) => self.evaluate(initializer)?,
+                    None => Value::Nil,
+                };
+                self.environment.borrow_mut().define(name.lexeme(), value);
+                Ok(Flow::Normal)
+            }
+            _ => Err(RuntimeError::new(stmt.span(), "Can't run this statement yet")),
+        }
+    }
+    fn print(&mut self, expr: &Expr, stmt: &Stmt, show: fn(&Value) -> String) -> RuntimeResult<Flow> {
+        let value = self.evaluate(expr)?;
+        self.write_line(&show(&value), stmt)?;
+        Ok(Flow::Normal)
+    }
+    // a Rust loop rather than recursion per iteration, so a long loop keeps a flat stack. The increment
+    // still runs after a `continue`, a for loop would otherwise spin on the same value.
+    fn while_loop(&mut self, condition: &Expr, body: &Stmt, increment: Option<&Expr>) -> RuntimeResult<Flow> {
+        while self.evaluate(condition)?.is_truthy() {
+            match self.execute(body)? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Normal | Flow::Continue => {}
+            }
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+    fn class(&mut self, name: &Token, superclass: Option<&Expr>, methods: &[Function]) -> RuntimeResult<Flow> {
+        if let Some(superclass) = superclass {
+            return Err(RuntimeError::new(superclass.span(), "Can't inherit from a class yet"));
+        }
+        // a later method with the same name replaces an earlier one
+        let methods = methods
+            .iter()
+            .map(|method| (method.name.lexeme().to_string(), Rc::new(self.function(method))))
+            .collect::<HashMap<_, _>>();
+        let class = LoxClass::new(name.lexeme(), methods);
+        self.environment.borrow_mut().define(name.lexeme(), Value::Class(Rc::new(class)));
+        Ok(Flow::Normal)
+    }
+    // A declaration closing over the current scope. It's copied out of the tree, which only lives as long as
+    // the run that parsed it (one REPL line), while the function may be called on any later one.
+    fn function(&self, declaration: &Function) -> LoxFunction {
+        LoxFunction::new(Rc::new(declaration.clone().into_owned()), Rc::clone(&self.environment))
+    }
+    // runs `statements` in `scope` up to the first one that fails or unwinds, then goes back to the current scope
+    pub(crate) fn execute_block(&mut self, statements: &[Stmt], scope: Environment) -> RuntimeResult<Flow> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope)));
+        let result = self.execute_all(statements);
+        self.environment = previous;
+        result
+    }
+    fn execute_all(&mut self, statements: &[Stmt]) -> RuntimeResult<Flow> {
+        for statement in statements {
+            let flow = self.execute(statement)?;
+            if flow != Flow::Normal {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+    pub fn evaluate(&mut self, expr: &Expr) -> RuntimeResult<Value> {
+        match &expr.kind {
+            ExprKind::Literal(literal) => Ok(literal_value(literal)),
+            ExprKind::Grouping(expr) => self.evaluate(expr),
+            ExprKind::Unary { operator, right } => self.unary(operator, right),
+            ExprKind::Binary { left, operator, right } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                self.binary(operator, left, right)
+            }
+            ExprKind::Logical { left, operator, right } => self.logical(left, operator, right),
+            ExprKind::Call { callee, paren, arguments } => self.call(callee, paren, arguments),
+            ExprKind::Get { object, name } => match self.evaluate(object)? {
+                Value::Instance(instance) => LoxInstance::get(&instance, name),
+                _ => Err(RuntimeError::new(name.span(), "Only instances have properties")),
+            },
+            ExprKind::Set { object, name, value } => self.set(object, name, value),
+            // defined by LoxFunction::bind in the scope around a method's body
+            ExprKind::This(keyword) => self.environment.borrow().get(keyword),
+            ExprKind::Variable(name) => self.environment.borrow().get(name),
+            // the assigned value is also the expression's, so `a = b = 1` sets both
+            ExprKind::Assign { name, value } => {
+                let value = self.evaluate(value)?;
+                self.environment.borrow_mut().assign(name, value.clone())?;
+                Ok(value)
+            }
+            _ => Err(RuntimeError::new(expr.span(), "Can't evaluate this expression yet")),
+        }
+    }
+    fn unary(&mut self, operator: &Token, right: &Expr) -> RuntimeResult<Value> {
+        let right = self.evaluate(right)?;
+        match operator.ttype() {
+            TokenType::Minus => Ok((-number_operand(operator, &right)?).into()),
+            TokenType::Bang => Ok((!right.is_truthy()).into()),
+            _ => unreachable!("the parser makes no other unary operator"),
+        }
+    }
+    // the deciding operand itself is the result, not a bool: `nil or "default"` is "default"
+    fn logical(&mut self, left: &Expr, operator: &Token, right: &Expr) -> RuntimeResult<Value> {
+        let left = self.evaluate(left)?;
+        let short_circuits = match operator.ttype() {
+            TokenType::Or => left.is_truthy(),
+            TokenType::And => !left.is_truthy(),
+            _ => unreachable!("the parser makes no other logical operator"),
+        };
+        if short_circuits {
+            Ok(left)
+        } else {
+            self.evaluate(right)
+        }
+    }
+    // The callee, then the arguments left to right, are all evaluated before anything is checked. Calls
+    // nested past max_call_stack are an error rather than a crash once the Rust stack runs out.
+    fn call(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> RuntimeResult<Value> {
+        let callee = self.evaluate(callee)?;
+        let arguments = arguments.iter().map(|argument| self.evaluate(argument)).collect::<RuntimeResult<Vec<_>>>()?;
+        let function: &dyn Callable = match &callee {
+            Value::Function(function) => function.as_ref(),
+            Value::Native(native) => native.as_ref(),
+            Value::Class(class) => class,
+            _ => return Err(RuntimeError::new(paren.span(), "Can only call functions and classes")),
+        };
+        if arguments.len() != function.arity() {
+            let message = format!("Expected {} arguments but got {}", function.arity(), arguments.len());
+            return Err(RuntimeError::new(paren.span(), &message));
+        }
+        let stack = stack_position();
+        if self.call_depth == 0 {
+            self.stack_base = stack;
+        }
+        if self.stack_base.abs_diff(stack) > self.max_call_stack {
+            return Err(RuntimeError::new(paren.span(), "Stack overflow"));
+        }
+        self.call_depth += 1;
+        let result = function.call(self, paren, arguments);
+        self.call_depth -= 1;
+        result
+    }
+    // the object is checked before the value is evaluated; like assignment, the value is the result
+    fn set(&mut self, object: &Expr, name: &Token, value: &Expr) -> RuntimeResult<Value> {
+        let Value::Instance(instance) = self.evaluate(object)? else {
+            return Err(RuntimeError::new(name.span(), "Only instances have fields"));
+        };
+        let value = self.evaluate(value)?;
+        instance.borrow_mut().set(name, value.clone());
+        Ok(value)
+    }
+    // the next line of input with its line break, like BufRead::read_line (0 bytes read at the end of input)
+    pub fn read_line(&mut self, line: &mut String) -> io::Result<usize> {
+        self.input.read_line(line)
+    }
+    // a failed write (a closed pipe) is reported at the statement doing it
+    fn write_line(&mut self, line: &str, stmt: &Stmt) -> RuntimeResult<()> {
+        writeln!(self.out, "{}", line)
+            .map_err(|error| RuntimeError::new(stmt.span(), &format!("Can't write output: {}", error)))
+    }
+    // both operands are evaluated, left first, before the operator looks at them
+    fn binary(&self, operator: &Token, left: Value, right: Value) -> RuntimeResult<Value> {
+        let value = match operator.ttype() {
+            // C's comma operator, the left side only ran for its effects
+            TokenType::Comma => right,
+            TokenType::EqualEqual => left.lox_eq(&right).into(),
+            TokenType::BangEqual => (!left.lox_eq(&right)).into(),
+            TokenType::Plus => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => (left + right).into(),
+                // appended to the left string's buffer, so a chain `a + b + c + ...` grows one string rather
+                // than copying every intermediate result
+                (Value::Str(mut left), Value::Str(right)) => {
+                    left.push_str(&right);
+                    left.into()
+                }
+                _ => return Err(RuntimeError::new(operator.span(), "Operands must be two numbers or two strings")),
+            },
+            _ => {
+                let (left, right) = number_operands(operator, &left, &right)?;
+                match operator.ttype() {
+                    TokenType::Minus => (left - right).into(),
+                    TokenType::Star => (left * right).into(),
+                    // 0 / 0 included, and -0 since it equals 0
+                    TokenType::Slash if right == 0.0 && !self.ieee_division => {
+                        return Err(RuntimeError::new(operator.span(), "Division by zero"));
+                    }
+                    TokenType::Slash => (left / right).into(),
+                    TokenType::Greater => (left > right).into(),
+                    TokenType::GreaterEqual => (left >= right).into(),
+                    TokenType::Less => (left < right).into(),
+                    TokenType::LessEqual => (left <= right).into(),
+                    _ => unreachable!("the parser makes no other binary operator"),
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+// the address of a local of the caller's frame, to measure how far the stack has grown between two calls
+#[inline(never)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+fn literal_value(literal: &Literal) -> Value {
+    match literal {
+        Literal::Number(number) => (*number).into(),
+        Literal::Str(string) => string.to_string().into(),
+        Literal::Bool(value) => (*value).into(),
+        Literal::Nil | Literal::None | Literal::Symbol(_) => Value::Nil,
+    }
+}
+
+fn number_operand(operator: &Token, operand: &Value) -> RuntimeResult<f64> {
+    match operand {
+        Value::Number(number) => Ok(*number),
+        _ => Err(RuntimeError::new(operator.span(), "Operand must be a number")),
+    }
+}
+
+fn number_operands(operator: &Token, left: &Value, right: &Value) -> RuntimeResult<(f64, f64)> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => Ok((*left, *right)),
+        _ => Err(RuntimeError::new(operator.span(), "Operands must be numbers")),
+    }
+}
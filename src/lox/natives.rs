@@ -0,0 +1,87 @@
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::lox::callable::NativeFunction;
+use crate::lox::environment::Environment;
+use crate::lox::interpreter::Interpreter;
+use crate::lox::value::Value;
+
+// Defines every native function as a global, done once when an Interpreter is made. A script can still
+// shadow or reassign them like any other global.
+pub fn define_natives(globals: &mut Environment) {
+    let natives = [
+        NativeFunction::new("clock", 0, clock),
+        NativeFunction::new("readLine", 0, read_line),
+        NativeFunction::new("readNumber", 0, read_number),
+        NativeFunction::new("len", 1, len),
+        NativeFunction::new("str", 1, str),
+        NativeFunction::new("num", 1, num),
+    ];
+    for native in natives {
+        globals.define(native.name, Value::Native(Rc::new(native)));
+    }
+}
+
+// seconds since the Unix epoch, with a fraction, so a script can time itself with `clock() - start`
+fn clock(_: &mut Interpreter, _: Vec<Value>) -> Result<Value, String> {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|error| error.to_string())?;
+    Ok(elapsed.as_secs_f64().into())
+}
+
+// the next input line without its line break, nil once the input is exhausted
+fn read_line(interpreter: &mut Interpreter, _: Vec<Value>) -> Result<Value, String> {
+    let mut line = String::new();
+    if interpreter.read_line(&mut line).map_err(|error| format!("Can't read input: {}", error))? == 0 {
+        return Ok(Value::Nil);
+    }
+    let len = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(len);
+    Ok(line.into())
+}
+
+// the next input line as a number (see parse_number), nil at the end of input or when it isn't one
+fn read_number(interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, String> {
+    match read_line(interpreter, arguments)? {
+        Value::Str(line) => Ok(parse_number(&line).map_or(Value::Nil, Value::Number)),
+        _ => Ok(Value::Nil),
+    }
+}
+
+// a string's length in characters (not bytes): `len("héllo")` is 5
+fn len(_: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Str(string) => Ok((string.chars().count() as f64).into()),
+        _ => Err("Argument must be a string".to_string()),
+    }
+}
+
+// what `print` would show, unquoted: `str(nil)` is "nil", `str(7)` is "7", a string is returned as is
+fn str(_: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, String> {
+    Ok(arguments[0].to_string().into())
+}
+
+// A string parsed as by parse_number, so `num(" 1 ")` is 1 while `num("")` and `num("1x")` are nil. A number
+// is returned unchanged, any other value gives nil; a failed conversion is never an error.
+fn num(_: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Str(string) => Ok(parse_number(string).map_or(Value::Nil, Value::Number)),
+        Value::Number(number) => Ok((*number).into()),
+        _ => Ok(Value::Nil),
+    }
+}
+
+// A number the way a Lox literal spells it, optionally negative and surrounded by whitespace: `12`, ` -0.5 `.
+// Rust's other float forms (`1e3`, `inf`, `.5`, `+1`) aren't numbers to a script.
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+    let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    if !all_digits(whole) || !fraction.is_none_or(all_digits) {
+        return None;
+    }
+    text.parse().ok()
+}
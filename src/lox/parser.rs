@@ -0,0 +1,918 @@
+use crate::lox::ast::{Expr, ExprKind, Function, Stmt, StmtKind};
+use crate::lox::error::ParseError;
+use crate::lox::tokens::{Literal, Span, Token, TokenType};
+
+// Recursive descent over the scanned tokens, one method per grammar rule, except for the infix operators
+// from comma to factor: parse_expr_bp handles those from the binding_power table.
+//
+// program     -> declaration* EOF
+// declaration -> classDecl | funDecl | varDecl | statement
+// classDecl   -> "class" IDENTIFIER ( "<" IDENTIFIER )? "{" function* "}"
+// funDecl     -> "fun" function
+// function    -> IDENTIFIER "(" parameters? ")" block
+// lambda      -> "fun" "(" parameters? ")" block
+// parameters  -> IDENTIFIER ( "," IDENTIFIER )*
+// varDecl     -> "var" IDENTIFIER ( "=" expression )? ";"
+// statement   -> printStmt | exprStmt | ifStmt | whileStmt | forStmt | switchStmt | returnStmt
+//              | breakStmt | continueStmt | block
+// returnStmt  -> "return" expression? ";" | "return" (right before a block's "}")
+// breakStmt   -> "break" ";"
+// continueStmt -> "continue" ";"
+// ifStmt      -> "if" "(" expression ")" statement ( "else" statement )?
+// whileStmt   -> "while" "(" expression ")" statement
+// forStmt     -> "for" "(" ( varDecl | exprStmt | ";" ) expression? ";" expression? ")" statement
+// switchStmt  -> "switch" "(" expression ")" "{" ( ( "case" expression | "default" ) ":" declaration* )* "}"
+// block       -> "{" declaration* "}"
+// printStmt   -> "print" expression ";"
+// exprStmt    -> expression ";"
+// expression  -> comma
+// comma       -> assignment ( "," assignment )*
+// assignment  -> ( call "." )? IDENTIFIER "=" assignment | call "[" expression "]" "=" assignment
+//              | conditional
+// conditional -> logic_or ( "?" expression ":" conditional )?
+// logic_or    -> logic_and ( "or" logic_and )*
+// logic_and   -> equality ( "and" equality )*
+// equality    -> comparison ( ( "!=" | "==" ) comparison )*
+// comparison  -> term ( ( ">" | ">=" | "<" | "<=" ) term )*
+// term        -> factor ( ( "-" | "+" ) factor )*
+// factor      -> unary ( ( "/" | "*" ) unary )*
+// unary       -> ( "!" | "-" ) unary | call
+// call        -> primary ( "(" arguments? ")" | "." IDENTIFIER | "[" expression "]" )*
+// arguments   -> assignment ( "," assignment )*
+// primary     -> NUMBER | STRING | "true" | "false" | "nil" | "this" | IDENTIFIER | "(" expression ")"
+//              | "super" "." IDENTIFIER | lambda | "[" ( assignment ( "," assignment )* ","? )? "]"
+//              | "?" (error production: a conditional with no condition)
+pub struct Parser<'src> {
+    // for the length of spans running over several lines
+    source: &'src str,
+    tokens: Vec<Token<'src>>,
+    current: usize,
+    // groupings opened and not closed yet, a ')' found with none open is a stray one
+    open_parens: usize,
+    // same for blocks and '}', these survive panic mode since recovery happens inside the block
+    open_braces: usize,
+    // loops around the statement being parsed, up to the closest function
+    loop_depth: usize,
+    // functions and methods around the statement being parsed, `return` needs one
+    function_depth: usize,
+    // statements and expressions around the one being parsed, each a few frames of the Rust stack
+    depth: usize,
+    // set once the input nests deeper than MAX_DEPTH: the rest of it is skipped, and the errors that only
+    // come from skipping it (blocks left unclosed) aren't reported
+    gave_up: bool,
+    // errors that don't need panic mode, the parse carries on right where it found them
+    errors: Vec<ParseError>,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+// most parameters a function, or arguments a call, can have
+const MAX_ARITY: usize = 255;
+
+// most statements and expressions that can be nested in one another, to fit a 2 MiB thread stack (a test's)
+// in a debug build
+const MAX_DEPTH: usize = 128;
+
+// The left and right binding powers of each infix operator, loosest first. Left-associative operators
+// bind tighter on the right, so `1 - 2 - 3` groups as `(1 - 2) - 3`; right-associative ones (`=`, `?:`)
+// the other way round. A ternary's else branch is parsed at the right power of '?', its then branch as a
+// whole expression since the ':' closes it.
+fn binding_power(operator: TokenType) -> Option<(u8, u8)> {
+    let bp = match operator {
+        // C's comma operator, evaluates both sides and yields the right one
+        TokenType::Comma => (1, 2),
+        TokenType::Equal => (4, ASSIGNMENT_BP),
+        TokenType::Question => (6, 5),
+        TokenType::Or => (7, 8),
+        TokenType::And => (9, 10),
+        TokenType::BangEqual | TokenType::EqualEqual => (11, 12),
+        TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual => (13, 14),
+        TokenType::Minus | TokenType::Plus => (15, 16),
+        TokenType::Slash | TokenType::Star => (17, 18),
+        _ => return None,
+    };
+    Some(bp)
+}
+
+// an expression parsed at this power stops before a comma operator
+const ASSIGNMENT_BP: u8 = 3;
+
+impl<'src> Parser<'src> {
+    // `tokens` are scanned from `source` and end with Eof, as the scanner leaves them; Comment tokens are
+    // dropped
+    pub fn new(source: &'src str, mut tokens: Vec<Token<'src>>) -> Self {
+        tokens.retain(|token| token.ttype() != TokenType::Comment);
+        Parser {
+            source,
+            tokens,
+            current: 0,
+            open_parens: 0,
+            open_braces: 0,
+            loop_depth: 0,
+            function_depth: 0,
+            depth: 0,
+            gave_up: false,
+            errors: Vec::new(),
+        }
+    }
+    // Every statement that parsed, and an error for each one that didn't: after an error the parser skips
+    // to the next statement and carries on, so one mistake doesn't hide the ones after it.
+    pub fn parse(&mut self) -> (Vec<Stmt<'src>>, Vec<ParseError>) {
+        let mut statements = Vec::new();
+        while !self.at_end() {
+            statements.extend(self.recovering_declaration());
+        }
+        (statements, std::mem::take(&mut self.errors))
+    }
+    // A line typed at the prompt: statements as in a script, or else a lone expression with no ';', which
+    // becomes an echoed expression statement. The errors are the statements' when neither parses.
+    pub fn parse_repl_line(&mut self) -> (Vec<Stmt<'src>>, Vec<ParseError>) {
+        let (statements, errors) = self.parse();
+        if errors.is_empty() {
+            return (statements, errors);
+        }
+        self.rewind();
+        match self.parse_expression() {
+            (Some(expr), expression_errors) if expression_errors.is_empty() => {
+                let span = expr.span();
+                (vec![Stmt::new(StmtKind::Expression { expr, echo: true }, span)], expression_errors)
+            }
+            _ => (Vec::new(), errors),
+        }
+    }
+    // back to the first token, to parse the input another way
+    fn rewind(&mut self) {
+        self.current = 0;
+        self.open_parens = 0;
+        self.open_braces = 0;
+        self.loop_depth = 0;
+        self.function_depth = 0;
+        self.depth = 0;
+        self.gave_up = false;
+        self.errors.clear();
+    }
+    // a lone expression making up the whole input, like `1 + 2 * 3` with no `;`
+    pub fn parse_expression(&mut self) -> (Option<Expr<'src>>, Vec<ParseError>) {
+        let expr = match self.expression() {
+            Ok(expr) if self.at_end() => Some(expr),
+            Ok(_) => {
+                self.errors.push(self.error(self.peek(), "Expected end of expression"));
+                None
+            }
+            Err(error) => {
+                if !self.gave_up {
+                    self.errors.push(error);
+                }
+                None
+            }
+        };
+        (expr, std::mem::take(&mut self.errors))
+    }
+    // a declaration, or None with the error recorded and the parser moved on to the next statement
+    fn recovering_declaration(&mut self) -> Option<Stmt<'src>> {
+        let start = self.current;
+        match self.declaration() {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                if !self.gave_up {
+                    self.errors.push(error);
+                }
+                self.synchronize(start);
+                None
+            }
+        }
+    }
+    fn declaration(&mut self) -> ParseResult<Stmt<'src>> {
+        self.nested("Statement", Self::any_declaration)
+    }
+    fn any_declaration(&mut self) -> ParseResult<Stmt<'src>> {
+        if self.matches(&[TokenType::Class]) {
+            return self.class_declaration();
+        }
+        // a `fun` with no name is a lambda, starting an expression statement
+        if self.check(TokenType::Fun) && self.check_next(TokenType::Identifier) {
+            let first = self.current;
+            self.advance();
+            let function = self.function("function")?;
+            return Ok(self.stmt(StmtKind::Function(function), first));
+        }
+        if self.matches(&[TokenType::Var]) {
+            return self.var_declaration();
+        }
+        self.any_statement()
+    }
+    fn class_declaration(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        let name = self.consume(TokenType::Identifier, "Expected class name")?.clone();
+        let superclass = if self.matches(&[TokenType::Less]) {
+            let superclass = self.consume(TokenType::Identifier, "Expected superclass name")?.clone();
+            Some(Expr::new(ExprKind::Variable(superclass.clone()), superclass.span()))
+        } else {
+            None
+        };
+        self.consume(TokenType::LeftBrace, "Expected '{' before class body")?;
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.at_end() {
+            if self.matches(&[TokenType::Semicolon]) {
+                // harmless, so reported and skipped without panic mode
+                let error = self.error(self.previous(), "Unexpected ';' between methods");
+                self.errors.push(error);
+                continue;
+            }
+            methods.push(self.function("method")?);
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after class body")?;
+        let class = StmtKind::Class {
+            name,
+            superclass,
+            methods,
+        };
+        Ok(self.stmt(class, first))
+    }
+    // `kind` names what's declared in error messages
+    fn function(&mut self, kind: &str) -> ParseResult<Function<'src>> {
+        let first = self.current;
+        let name = self.consume(TokenType::Identifier, &format!("Expected {} name", kind))?.clone();
+        self.consume(TokenType::LeftParen, &format!("Expected '(' after {} name", kind))?;
+        let (params, body) = self.parameters_and_body(kind)?;
+        Ok(Function {
+            name,
+            params,
+            body,
+            span: self.span_from(first),
+        })
+    }
+    // what follows the '(' of a named function or a lambda
+    fn parameters_and_body(&mut self, kind: &str) -> ParseResult<(Vec<Token<'src>>, Vec<Stmt<'src>>)> {
+        self.open_parens += 1;
+        let mut params = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if params.len() == MAX_ARITY {
+                    // the declaration is fine otherwise, so no panic mode
+                    let error = self.error(self.peek(), &format!("Can't have more than {} parameters", MAX_ARITY));
+                    self.errors.push(error);
+                }
+                params.push(self.consume(TokenType::Identifier, "Expected parameter name")?.clone());
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
+        self.open_parens -= 1;
+        self.consume(TokenType::LeftBrace, &format!("Expected '{{' before {} body", kind))?;
+        // a loop around a function doesn't let its body break out of it
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        self.loop_depth = loop_depth;
+        Ok((params, body?))
+    }
+    fn var_declaration(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        if self.peek().ttype().is_keyword() {
+            // consumed, or synchronize would stop at it and read it as the start of the next statement
+            let keyword = self.advance();
+            let message = format!("Can't use keyword '{}' as a variable name", keyword.lexeme());
+            return Err(self.error(self.previous(), &message));
+        }
+        let name = self.consume(TokenType::Identifier, "Expected variable name")?.clone();
+        let initializer = if self.matches(&[TokenType::Equal]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::Semicolon, "Expected ';' after variable declaration")?;
+        Ok(self.stmt(StmtKind::Var { name, initializer }, first))
+    }
+    fn statement(&mut self) -> ParseResult<Stmt<'src>> {
+        self.nested("Statement", Self::any_statement)
+    }
+    fn any_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        if self.matches(&[TokenType::Print]) {
+            return self.print_statement();
+        }
+        if self.matches(&[TokenType::If]) {
+            return self.if_statement();
+        }
+        if self.matches(&[TokenType::While]) {
+            return self.while_statement();
+        }
+        if self.matches(&[TokenType::For]) {
+            return self.for_statement();
+        }
+        if self.matches(&[TokenType::Switch]) {
+            return self.switch_statement();
+        }
+        if self.matches(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+        if self.matches(&[TokenType::Break, TokenType::Continue]) {
+            return self.loop_jump();
+        }
+        if self.matches(&[TokenType::LeftBrace]) {
+            let first = self.current - 1;
+            let statements = self.block()?;
+            return Ok(self.stmt(StmtKind::Block(statements), first));
+        }
+        if self.check(TokenType::Else) {
+            return Err(self.error(self.peek(), "Found 'else' without a matching 'if'"));
+        }
+        if self.check_label() {
+            let message = format!("Found '{}' outside a switch", self.peek().lexeme());
+            return Err(self.error(self.peek(), &message));
+        }
+        self.expression_statement()
+    }
+    fn if_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        let condition = self.condition("if")?;
+        let then_branch = self.statement()?;
+        // an `else` goes to the closest `if`, the one whose then branch was just parsed
+        let else_branch = if self.matches(&[TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+        let if_else = StmtKind::If {
+            condition,
+            then_branch: Box::new(then_branch),
+            else_branch,
+        };
+        Ok(self.stmt(if_else, first))
+    }
+    fn while_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        let condition = self.condition("while")?;
+        let body = self.loop_body()?;
+        let while_loop = StmtKind::While {
+            condition,
+            body: Box::new(body),
+            increment: None,
+        };
+        Ok(self.stmt(while_loop, first))
+    }
+    // There's no For node: `for (init; cond; incr) body` becomes `{ init; while (cond) body }` with `incr`
+    // as the while's increment, the outer block scoping a variable declared in `init`. The increment isn't
+    // simply appended to the body so that a `continue` in the body still runs it, as in C. A missing
+    // condition loops forever. The nodes made up for it all span the whole for statement.
+    fn for_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'for'")?;
+        self.open_parens += 1;
+        let initializer = if self.matches(&[TokenType::Semicolon]) {
+            None
+        } else if self.matches(&[TokenType::Var]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+        let condition = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expected ';' after loop condition")?;
+        let increment = if self.check(TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::RightParen, "Expected ')' after for clauses")?;
+        self.open_parens -= 1;
+
+        let body = self.loop_body()?;
+        let span = self.span_from(first);
+        let while_loop = StmtKind::While {
+            condition: condition.unwrap_or(Expr::new(ExprKind::Literal(Literal::Bool(true)), span)),
+            body: Box::new(body),
+            increment,
+        };
+        let mut body = Stmt::new(while_loop, span);
+        if let Some(initializer) = initializer {
+            body = Stmt::new(StmtKind::Block(vec![initializer, body]), span);
+        }
+        Ok(body)
+    }
+    fn switch_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'switch'")?;
+        self.open_parens += 1;
+        // the arms can still be parsed, nil standing in for the missing value
+        let scrutinee = if self.check(TokenType::RightParen) {
+            self.errors.push(self.error(self.peek(), "Expected a value to switch on"));
+            Expr::new(ExprKind::Literal(Literal::Nil), self.peek().span())
+        } else {
+            self.expression()?
+        };
+        self.consume(TokenType::RightParen, "Expected ')' after switch value")?;
+        self.open_parens -= 1;
+        let open = self.consume(TokenType::LeftBrace, "Expected '{' before switch arms")?.clone();
+
+        let mut arms = Vec::new();
+        let mut has_default = false;
+        self.open_braces += 1;
+        while !self.check(TokenType::RightBrace) && !self.at_end() {
+            let label = self.peek().clone();
+            // like a block, a bad label is recovered from inside the switch, at the next label
+            let value = match self.switch_label() {
+                Ok(value) => value,
+                Err(error) => {
+                    self.errors.push(error);
+                    while !self.check_label() && !self.check(TokenType::RightBrace) && !self.at_end() {
+                        self.skip();
+                    }
+                    self.open_parens = 0;
+                    continue;
+                }
+            };
+            if value.is_none() {
+                if has_default {
+                    self.errors.push(self.error(&label, "A switch can only have one 'default'"));
+                }
+                has_default = true;
+            }
+            let mut body = Vec::new();
+            while !self.check_label() && !self.check(TokenType::RightBrace) && !self.at_end() {
+                body.extend(self.recovering_declaration());
+            }
+            arms.push((value, body));
+        }
+        self.open_braces -= 1;
+        if self.at_end() {
+            return Err(self.error(&open, "Expected '}' to close this switch"));
+        }
+        self.advance();
+        Ok(self.stmt(StmtKind::Switch { scrutinee, arms }, first))
+    }
+    // `case value:` or `default:`, None for the latter
+    fn switch_label(&mut self) -> ParseResult<Option<Expr<'src>>> {
+        if self.matches(&[TokenType::Case]) {
+            let value = self.expression()?;
+            self.consume(TokenType::Colon, "Expected ':' after case value")?;
+            return Ok(Some(value));
+        }
+        if self.matches(&[TokenType::Default]) {
+            self.consume(TokenType::Colon, "Expected ':' after 'default'")?;
+            return Ok(None);
+        }
+        Err(self.error(self.peek(), "Expected 'case' or 'default'"))
+    }
+    fn check_label(&self) -> bool {
+        self.check(TokenType::Case) || self.check(TokenType::Default)
+    }
+    fn loop_body(&mut self) -> ParseResult<Stmt<'src>> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+    // `break` or `continue`, whichever was just matched
+    fn loop_jump(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        let keyword = self.previous().clone();
+        if self.loop_depth == 0 {
+            // nothing wrong with the statement itself, so no panic mode
+            let message = format!("Must be inside a loop to use '{}'", keyword.lexeme());
+            self.errors.push(self.error(&keyword, &message));
+        }
+        self.consume(TokenType::Semicolon, &format!("Expected ';' after '{}'", keyword.lexeme()))?;
+        if keyword.ttype() == TokenType::Break {
+            return Ok(self.stmt(StmtKind::Break(keyword), first));
+        }
+        Ok(self.stmt(StmtKind::Continue(keyword), first))
+    }
+    // the parenthesized condition after `keyword`
+    fn condition(&mut self, keyword: &str) -> ParseResult<Expr<'src>> {
+        self.consume(TokenType::LeftParen, &format!("Expected '(' after '{}'", keyword))?;
+        self.open_parens += 1;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, &format!("Expected ')' after {} condition", keyword))?;
+        self.open_parens -= 1;
+        Ok(condition)
+    }
+    // the statements after a '{' up to its '}'; errors inside recover inside, so one bad statement doesn't
+    // end the block early
+    fn block(&mut self) -> ParseResult<Vec<Stmt<'src>>> {
+        let open = self.previous().clone();
+        let mut statements = Vec::new();
+        self.open_braces += 1;
+        while !self.check(TokenType::RightBrace) && !self.at_end() {
+            statements.extend(self.recovering_declaration());
+        }
+        self.open_braces -= 1;
+        if self.at_end() {
+            // the end of input says nothing about where the '}' was forgotten, the opener at least tells which block
+            return Err(self.error(&open, "Expected '}' to close this block"));
+        }
+        self.advance();
+        Ok(statements)
+    }
+    fn print_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after value")?;
+        Ok(self.stmt(StmtKind::Print(value), first))
+    }
+    fn return_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current - 1;
+        // kept so a return outside any function can be reported where it is
+        let keyword = self.previous().clone();
+        if self.function_depth == 0 {
+            // nothing wrong with the statement itself, so no panic mode
+            self.errors.push(self.error(&keyword, "Can't return from top-level code"));
+        }
+        // `{ return }` is a bare return, the ';' can go with nothing after it in the block
+        if self.check(TokenType::RightBrace) {
+            return Ok(self.stmt(StmtKind::Return { keyword, value: None }, first));
+        }
+        let value = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expected ';' after return value")?;
+        Ok(self.stmt(StmtKind::Return { keyword, value }, first))
+    }
+    fn expression_statement(&mut self) -> ParseResult<Stmt<'src>> {
+        let first = self.current;
+        let expr = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after expression")?;
+        Ok(self.stmt(StmtKind::Expression { expr, echo: false }, first))
+    }
+    fn expression(&mut self) -> ParseResult<Expr<'src>> {
+        self.parse_expr_bp(0)
+    }
+    // Comma-separated lists (call arguments, array elements) parse their items at this level, right above
+    // the comma operator, or `f(a, b)` would be a call with the single argument `a, b`.
+    fn assignment(&mut self) -> ParseResult<Expr<'src>> {
+        self.parse_expr_bp(ASSIGNMENT_BP)
+    }
+    // Pratt loop over the infix operators of binding_power: an operand, then operators as long as they
+    // bind at least as tightly as `min_bp`, each taking a right operand parsed at its right binding power.
+    fn parse_expr_bp(&mut self, min_bp: u8) -> ParseResult<Expr<'src>> {
+        self.nested("Expression", |parser| parser.infix(min_bp))
+    }
+    fn infix(&mut self, min_bp: u8) -> ParseResult<Expr<'src>> {
+        let first = self.current;
+        let mut expr = self.unary()?;
+        while let Some((left_bp, right_bp)) = binding_power(self.peek().ttype()) {
+            if left_bp < min_bp {
+                break;
+            }
+            expr = self.operator(expr, right_bp, first)?;
+        }
+        Ok(expr)
+    }
+    // the operator about to be consumed and its right operand, `left` starting at tokens[first]
+    fn operator(&mut self, left: Expr<'src>, right_bp: u8, first: usize) -> ParseResult<Expr<'src>> {
+        let operator = self.advance().clone();
+        match operator.ttype() {
+            TokenType::Equal => {
+                let value = self.parse_expr_bp(right_bp)?;
+                Ok(self.assign(left, &operator, value, first))
+            }
+            TokenType::Question => self.ternary(left, right_bp, first),
+            _ => {
+                let right = self.parse_expr_bp(right_bp)?;
+                Ok(self.binary(left, operator, right, first))
+            }
+        }
+    }
+    fn ternary(&mut self, condition: Expr<'src>, right_bp: u8, first: usize) -> ParseResult<Expr<'src>> {
+        let then_branch = self.expression()?;
+        self.consume(TokenType::Colon, "Expected ':' after the then branch of a conditional")?;
+        let else_branch = self.parse_expr_bp(right_bp)?;
+        let ternary = ExprKind::Ternary {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        };
+        Ok(self.expr(ternary, first))
+    }
+    fn binary(&self, left: Expr<'src>, operator: Token<'src>, right: Expr<'src>, first: usize) -> Expr<'src> {
+        let (left, right) = (Box::new(left), Box::new(right));
+        let kind = match operator.ttype() {
+            // a Logical node since the right operand may never be evaluated
+            TokenType::And | TokenType::Or => ExprKind::Logical { left, operator, right },
+            _ => ExprKind::Binary { left, operator, right },
+        };
+        self.expr(kind, first)
+    }
+    // The target is parsed as an ordinary expression and checked once the '=' shows up, there's no telling
+    // `a.b.c = 1` from `a.b.c;` before that. A bad target is reported without panic mode since the rest
+    // of the expression is fine, and the target is kept as it was.
+    fn assign(&mut self, target: Expr<'src>, equals: &Token<'src>, value: Expr<'src>, first: usize) -> Expr<'src> {
+        let value = Box::new(value);
+        let assignment = match target.kind {
+            ExprKind::Variable(name) => ExprKind::Assign { name, value },
+            // what was parsed as a property read becomes a write
+            ExprKind::Get { object, name } => ExprKind::Set { object, name, value },
+            ExprKind::Index { object, bracket, index } => ExprKind::IndexSet {
+                object,
+                bracket,
+                index,
+                value,
+            },
+            kind => {
+                self.errors.push(self.error(equals, "Invalid assignment target"));
+                return Expr::new(kind, target.span);
+            }
+        };
+        self.expr(assignment, first)
+    }
+    fn unary(&mut self) -> ParseResult<Expr<'src>> {
+        if matches!(self.peek().ttype(), TokenType::Bang | TokenType::Minus | TokenType::MinusMinus) {
+            return self.nested("Expression", Self::prefix);
+        }
+        self.call()
+    }
+    // the prefix operator about to be consumed and its operand
+    fn prefix(&mut self) -> ParseResult<Expr<'src>> {
+        let first = self.current;
+        if self.matches(&[TokenType::Bang, TokenType::Minus]) {
+            let operator = self.previous().clone();
+            let right = self.unary()?;
+            let unary = ExprKind::Unary {
+                operator,
+                right: Box::new(right),
+            };
+            return Ok(self.expr(unary, first));
+        }
+        // the scanner reads `--` as one token, as a prefix it's a negation of a negation
+        let (outer, inner) = self.advance().halves(TokenType::Minus);
+        let right = self.unary()?;
+        let span = self.span_from(first);
+        let inner = ExprKind::Unary {
+            operator: inner,
+            right: Box::new(right),
+        };
+        let inner_span = Span::new(span.line, span.col + 1, span.len - 1);
+        let outer = ExprKind::Unary {
+            operator: outer,
+            right: Box::new(Expr::new(inner, inner_span)),
+        };
+        Ok(Expr::new(outer, span))
+    }
+    // left-associative, `f(1)(2)` calls what `f(1)` returns and `a.b(1).c` reads c off that call's result
+    fn call(&mut self) -> ParseResult<Expr<'src>> {
+        let first = self.current;
+        let primary = self.primary()?;
+        self.postfix(primary, first)
+    }
+    // the calls, property reads and indexing after an operand starting at tokens[first]
+    fn postfix(&mut self, mut expr: Expr<'src>, first: usize) -> ParseResult<Expr<'src>> {
+        loop {
+            if self.matches(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr, first)?;
+            } else if self.matches(&[TokenType::Dot]) {
+                expr = self.property(expr, first)?;
+            } else if self.matches(&[TokenType::LeftBracket]) {
+                expr = self.index(expr, first)?;
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+    fn property(&mut self, object: Expr<'src>, first: usize) -> ParseResult<Expr<'src>> {
+        let name = self.consume(TokenType::Identifier, "Expected property name after '.'")?.clone();
+        let get = ExprKind::Get {
+            object: Box::new(object),
+            name,
+        };
+        Ok(self.expr(get, first))
+    }
+    fn index(&mut self, object: Expr<'src>, first: usize) -> ParseResult<Expr<'src>> {
+        if self.check(TokenType::RightBracket) {
+            return Err(self.error(self.peek(), "Expected index expression"));
+        }
+        let index = self.expression()?;
+        // kept on the node so runtime errors (out of bounds, not an array) can point at it
+        let bracket = self.consume(TokenType::RightBracket, "Expected ']' after index")?.clone();
+        let index = ExprKind::Index {
+            object: Box::new(object),
+            bracket,
+            index: Box::new(index),
+        };
+        Ok(self.expr(index, first))
+    }
+    // `first` is the callee's first token
+    fn finish_call(&mut self, callee: Expr<'src>, first: usize) -> ParseResult<Expr<'src>> {
+        self.open_parens += 1;
+        let mut arguments = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if arguments.len() == MAX_ARITY {
+                    let error = self.error(self.peek(), &format!("Can't have more than {} arguments", MAX_ARITY));
+                    self.errors.push(error);
+                }
+                // below comma, or its commas would be taken for the comma operator
+                arguments.push(self.assignment()?);
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        // kept on the node so runtime errors can point at the call
+        let paren = self.consume(TokenType::RightParen, "Expected ')' after arguments")?.clone();
+        self.open_parens -= 1;
+        let call = ExprKind::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        };
+        Ok(self.expr(call, first))
+    }
+    // A literal, name, `this`, `super.method`, array, lambda or grouping. A grouping is the one primary
+    // nesting further expressions, split from the others so that the frames of every level of parentheses
+    // stay small.
+    fn primary(&mut self) -> ParseResult<Expr<'src>> {
+        if self.check(TokenType::LeftParen) {
+            return self.grouping();
+        }
+        self.atom()
+    }
+    fn grouping(&mut self) -> ParseResult<Expr<'src>> {
+        let first = self.current;
+        self.advance();
+        self.open_parens += 1;
+        let expr = self.expression()?;
+        self.consume(TokenType::RightParen, "Expected ')' after expression")?;
+        self.open_parens -= 1;
+        Ok(self.expr(ExprKind::Grouping(Box::new(expr)), first))
+    }
+    fn atom(&mut self) -> ParseResult<Expr<'src>> {
+        let first = self.current;
+        let kind = match self.peek().ttype() {
+            TokenType::Number | TokenType::String | TokenType::True | TokenType::False | TokenType::Nil => {
+                ExprKind::Literal(self.advance().literal().clone())
+            }
+            TokenType::This => ExprKind::This(self.advance().clone()),
+            // only ever `super.method`, a superclass isn't a value of its own
+            TokenType::Super => {
+                let keyword = self.advance().clone();
+                self.consume(TokenType::Dot, "Expected '.' after 'super'")?;
+                let method = self.consume(TokenType::Identifier, "Expected superclass method name")?.clone();
+                ExprKind::Super { keyword, method }
+            }
+            TokenType::Identifier => ExprKind::Variable(self.advance().clone()),
+            TokenType::LeftBracket => self.array_literal()?,
+            TokenType::Fun => self.lambda()?,
+            TokenType::Question => return Err(self.error(self.peek(), "Expected a condition before '?'")),
+            _ => return Err(self.error(self.peek(), "Expected expression")),
+        };
+        Ok(self.expr(kind, first))
+    }
+
+    fn array_literal(&mut self) -> ParseResult<ExprKind<'src>> {
+        self.advance();
+        let mut elements = Vec::new();
+        while !self.check(TokenType::RightBracket) {
+            // below comma, like call arguments
+            elements.push(self.assignment()?);
+            if !self.matches(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBracket, "Expected ']' after array elements")?;
+        Ok(ExprKind::ArrayLiteral(elements))
+    }
+    fn lambda(&mut self) -> ParseResult<ExprKind<'src>> {
+        self.advance();
+        self.consume(TokenType::LeftParen, "Expected '(' after 'fun'")?;
+        let (params, body) = self.parameters_and_body("function")?;
+        Ok(ExprKind::Lambda { params, body })
+    }
+
+    // Panic mode: drops tokens up to the end of the statement that failed, `start` being where it began.
+    // A statement keyword or a block's braces stop it even where the error was found, so a missing ';'
+    // doesn't swallow the next line, nor a missing ')' the loop body after it; at least one token is
+    // dropped if the failed statement consumed none. A ';' inside parentheses left open by the error
+    // separates for clauses rather than statements, so it doesn't stop it.
+    fn synchronize(&mut self, start: usize) {
+        if self.current == start {
+            self.skip();
+        }
+        while !self.at_end() {
+            if self.previous().ttype() == TokenType::Semicolon && self.open_parens == 0 {
+                break;
+            }
+            match self.peek().ttype() {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Switch
+                | TokenType::Case
+                | TokenType::Default
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::LeftBrace
+                | TokenType::RightBrace => break,
+                _ => {}
+            }
+            self.skip();
+        }
+        self.open_parens = 0;
+    }
+    // drops a token for synchronize, counting the parentheses it opens and closes
+    fn skip(&mut self) {
+        match self.advance().ttype() {
+            TokenType::LeftParen => self.open_parens += 1,
+            TokenType::RightParen => self.open_parens = self.open_parens.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    fn matches(&mut self, types: &[TokenType]) -> bool {
+        if types.iter().any(|&ttype| self.check(ttype)) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+    fn consume(&mut self, ttype: TokenType, message: &str) -> ParseResult<&Token<'src>> {
+        if self.check(ttype) {
+            return Ok(self.advance());
+        }
+        // a token missing at the end of a line (usually a ';') is pointed at there, not at the next line
+        if self.current > 0 && !self.at_end() && self.peek().span().line > self.previous().span().line {
+            return Err(ParseError::new(self.after_previous(), message.to_string()));
+        }
+        Err(self.error(self.peek(), message))
+    }
+    fn check(&self, ttype: TokenType) -> bool {
+        !self.at_end() && self.peek().ttype() == ttype
+    }
+    fn check_next(&self, ttype: TokenType) -> bool {
+        self.tokens.get(self.current + 1).is_some_and(|token| token.ttype() == ttype)
+    }
+    fn advance(&mut self) -> &Token<'src> {
+        if !self.at_end() {
+            self.current += 1;
+        }
+        self.previous()
+    }
+    fn at_end(&self) -> bool {
+        self.peek().ttype() == TokenType::Eof
+    }
+    fn peek(&self) -> &Token<'src> {
+        &self.tokens[self.current]
+    }
+    fn previous(&self) -> &Token<'src> {
+        &self.tokens[self.current - 1]
+    }
+    // `parse` one level deeper, or past MAX_DEPTH an error at the current token rather than running out of
+    // stack; `what` is nested in the error message
+    fn nested<T>(&mut self, what: &str, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth == MAX_DEPTH {
+            let error = self.error(self.peek(), &format!("{} nested too deeply", what));
+            self.errors.push(error.clone());
+            self.gave_up = true;
+            self.current = self.tokens.len() - 1;
+            return Err(error);
+        }
+        self.depth += 1;
+        let parsed = parse(self);
+        self.depth -= 1;
+        parsed
+    }
+    fn error(&self, token: &Token, message: &str) -> ParseError {
+        if token.ttype() == TokenType::RightParen && self.open_parens == 0 {
+            return ParseError::new(token.span(), "Unmatched ')'".to_string());
+        }
+        if token.ttype() == TokenType::RightBrace && self.open_braces == 0 {
+            return ParseError::new(token.span(), "Unmatched '}'".to_string());
+        }
+        // the Eof token sits on the line after a final newline, so point right after the last token instead
+        if token.ttype() == TokenType::Eof && self.current > 0 {
+            return ParseError::new(self.after_previous(), format!("{} at end of input", message));
+        }
+        ParseError::new(token.span(), message.to_string())
+    }
+    // from the start of tokens[first] to the end of the last token consumed
+    fn span_from(&self, first: usize) -> Span {
+        let (start, end) = (&self.tokens[first], self.previous());
+        let len = if start.span().line == end.span().line {
+            end.span().col + end.span().len - start.span().col
+        } else {
+            self.source[start.bytes().start..end.bytes().end].chars().count()
+        };
+        Span::new(start.span().line, start.span().col, len)
+    }
+    fn expr(&self, kind: ExprKind<'src>, first: usize) -> Expr<'src> {
+        Expr::new(kind, self.span_from(first))
+    }
+    fn stmt(&self, kind: StmtKind<'src>, first: usize) -> Stmt<'src> {
+        Stmt::new(kind, self.span_from(first))
+    }
+    fn after_previous(&self) -> Span {
+        let previous = self.previous().span();
+        Span::new(previous.line, previous.col + previous.len, 1)
+    }
+}
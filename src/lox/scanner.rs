@@ -1,16 +1,18 @@
-use std::{collections::HashMap, process};
+use std::{borrow::Cow, io::{self, BufRead}, str::FromStr};
 
-use crate::lox::tokens::{Token, TokenType};
-use crate::lox::error::report_error;
+use crate::lox::interner::Interner;
+use crate::lox::tokens::{Literal, Token, TokenType};
+use crate::lox::error::ScanError;
 
-pub struct Scanner {
-    pub source: String,
-    pub tokens: Vec<Token>,
-}
-
-struct EnumeratedString {
-    pub soken: String,
-    pub offset: usize,
+pub struct Scanner<'src> {
+    pub source: &'src str,
+    pub tokens: Vec<Token<'src>>,
+    pub errors: Vec<ScanError>,
+    // emit line comments as Comment tokens instead of skipping them, for formatters and doc tools
+    pub comments: bool,
+    // line number of the source's first line, past 1 for the later chunks of scan_reader
+    first_line: usize,
+    interner: Interner,
 }
 
 // digraph {
@@ -18,6 +20,13 @@ struct EnumeratedString {
 //     IdentifierOrKeyword -> IdentifierOrKeyword [ label="buf" ]
 //     Next -> InString [ label="buf" ]
 //     InString -> InString [ label="buf" ]
+//     InString -> InStringEscape [ label="nothing" ]
+//     InStringEscape -> InString [ label="buf" ]
+//     IdentifierOrKeyword -> InRawString [ label="nothing" ] ('r' then '"')
+//     InRawString -> InRawString [ label="buf" ]
+//     InRawString -> Next [ label="new" ]
+//     InString -> Next [ label="new" ] ("${")
+//     Next -> InString [ label="new" ] ('}' closing the "${")
 //     Next -> MaybeTwo [ label="buf" ]
 //     Next -> Next [ label="new" ]
 //     Next -> IdentifierOrKeyword [ label="buf" ]
@@ -32,11 +41,20 @@ struct EnumeratedString {
 //     Number -> NumberWithDot [ label="buf" ]
 //     NumberWithDot -> NumberWithDot [ label="buf" ]
 //     NumberWithDot -> Next [ label="new" ]
+//     Number -> NumberExponent [ label="buf" ]
+//     NumberWithDot -> NumberExponent [ label="buf" ]
+//     NumberExponent -> NumberExponentSign [ label="buf" ]
+//     NumberExponent -> NumberWithExponent [ label="buf" ]
+//     NumberExponentSign -> NumberWithExponent [ label="buf" ]
+//     NumberWithExponent -> NumberWithExponent [ label="buf" ]
+//     NumberWithExponent -> Next [ label="new" ]
 //     SoloDot -> NumberWithDot [ label="buf" ]
-//     SoloDot -> Next [ label="new" ]
+//     SoloDot -> Next [ label="new/replay" ]
 //     Comment -> Next [ label="nothing" ]
-//     IdentifierOrKeyword -> SoloDot [ label="new/buf" ]
-//     IdentifierOrKeyword -> MaybeTwo [ label="new/buf" ]
+//     IdentifierOrKeyword -> Next [ label="new/replay" ]
+//     MaybeTwo -> Next [ label="new/replay" ]
+//     Skip -> Skip [ label="nothing" ]
+//     Skip -> Next [ label="replay" ]
 //     BlockComment -> BlockCommentMaybeAdd [ label="nothing" ]
 //     BlockComment -> BlockCommentMaybeSubtract [ label="nothing" ]
 //     BlockComment -> BlockComment [ label="nothing" ]
@@ -124,6 +142,18 @@ struct EnumeratedString {
 // │                                           │   BlockCommentMaybeAdd    │           │
 // └────────────────────────────────────────── │                           │ ◀─────────┘
 //                                             └───────────────────────────┘
+// One entry per open "${" inside a string literal
+struct Interpolation {
+    // '{' opened inside the interpolation and not closed yet, the '}' seen at 0 ends it
+    braces: usize,
+    // (line, col) of the "${"
+    start: (usize, usize),
+    // string_start of the literal to resume after the closing '}'
+    string_start: (usize, usize),
+}
+
+// BlockComment* carry the nesting depth; string literals aren't recognized inside comments,
+// so a "*/" inside quotes still closes the comment.
 enum ScannerState {
     BlockComment(u8),
     BlockCommentMaybeAdd(u8),
@@ -133,69 +163,132 @@ enum ScannerState {
     MaybeTwo,
     IdentifierOrKeyword,
     InString,
+    InStringEscape,
+    InRawString,
     Number,
     NumberWithDot,
+    NumberExponent,
+    NumberExponentSign,
+    NumberWithExponent,
     SoloDot,
+    // dropping the rest of a bad lexeme, see is_resync_point
+    Skip,
 }
 
-impl Scanner {
-    pub fn new(code: String) -> Self {
+impl<'src> Scanner<'src> {
+    pub fn new(code: &'src str) -> Self {
         Scanner {
             source: code,
             tokens: Vec::new(),
+            errors: Vec::new(),
+            comments: false,
+            first_line: 1,
+            interner: Interner::default(),
         }
     }
-    pub fn scan_tokens(&mut self) {
-        let single_char: HashMap<char, TokenType> = HashMap::from([
-            ('(', TokenType::LeftParen),
-            (')', TokenType::RightParen),
-            ('{', TokenType::LeftBrace),
-            ('}', TokenType::RightBrace),
-            (',', TokenType::Comma),
-            ('.', TokenType::Dot),
-            ('-', TokenType::Minus),
-            ('+', TokenType::Plus),
-            (';', TokenType::Semicolon),
-            ('*', TokenType::Star),
-        ]);
-
-        let first_two_char: HashMap<char, TokenType> = HashMap::from([
-            ('!', TokenType::Bang),
-            ('=', TokenType::Equal),
-            ('>', TokenType::Greater),
-            ('<', TokenType::Less),
-            ('/', TokenType::Slash),
-        ]);
+    // resolves the symbols on Identifier tokens back to names
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+    pub fn scan_tokens(&mut self) -> Result<&[Token<'src>], Vec<ScanError>> {
+        // errors are collected into self.errors and the scan carries on, so every one of them gets reported
+        self.scan();
 
-        let keywords: HashMap<String, TokenType> = HashMap::from([
-            ("and".to_string(),     TokenType::And),
-            ("class".to_string(),   TokenType::Class),
-            ("else".to_string(),    TokenType::Else),
-            ("false".to_string(),   TokenType::False),
-            ("fun".to_string(),     TokenType::Fun),
-            ("for".to_string(),     TokenType::For),
-            ("if".to_string(),      TokenType::If),
-            ("nil".to_string(),     TokenType::Nil),
-            ("or".to_string(),      TokenType::Or),
-            ("print".to_string(),   TokenType::Print),
-            ("return".to_string(),  TokenType::Return),
-            ("super".to_string(),   TokenType::Super),
-            ("this".to_string(),    TokenType::This),
-            ("true".to_string(),    TokenType::True),
-            ("var".to_string(),     TokenType::Var),
-            ("while".to_string(),   TokenType::While),
-        ]);
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
 
-        let mut state = ScannerState::Next;
-        let mut buffer_vec: Vec<char> = Vec::with_capacity(128);
+        Ok(&self.tokens)
+    }
+    // Scans `reader` a line at a time, or a few when a string, block comment or interpolation spans them,
+    // so the whole input never has to be in memory. Each chunk's scanner is handed to `each`; lines and
+    // byte ranges count from the start of the input, and only the last chunk ends with an Eof token.
+    pub fn scan_reader(mut reader: impl BufRead, comments: bool, mut each: impl FnMut(&Scanner)) -> io::Result<()> {
+        let mut chunk = String::new();
+        let (mut first_line, mut first_byte) = (1usize, 0usize);
+        // one interner for all chunks, so a name gets the same symbol wherever it appears
+        let mut interner = Interner::default();
+        loop {
+            let read = reader.read_line(&mut chunk)?;
+            // read_line only stops without a '\n' at the end of input
+            let at_end = read == 0 || !chunk.ends_with('\n');
+            let mut scanner = Scanner::new(&chunk);
+            scanner.comments = comments;
+            scanner.first_line = first_line;
+            scanner.interner = std::mem::take(&mut interner);
+            if !scanner.scan() && !at_end {
+                interner = scanner.interner;
+                continue;
+            }
+            if !at_end {
+                scanner.tokens.pop();
+            }
+            for token in &mut scanner.tokens {
+                token.shift_bytes(first_byte);
+            }
+            each(&scanner);
+            interner = scanner.interner;
+            if at_end {
+                return Ok(());
+            }
+            first_line += chunk.matches('\n').count();
+            first_byte += chunk.len();
+            chunk.clear();
+        }
+    }
+    // text of `line` (numbered like token lines) for error excerpts
+    pub fn line_text(&self, line: usize) -> &'src str {
+        self.source.lines().nth(line - self.first_line).unwrap_or_default()
+    }
+    // false when the input ended inside a string, block comment or interpolation
+    fn scan(&mut self) -> bool {
+        // a "#!" shebang is only allowed as the very first bytes and is skipped like a line comment
+        let mut state = if self.first_line == 1 && self.source.starts_with("#!") {
+            ScannerState::Comment
+        } else {
+            ScannerState::Next
+        };
+        // tokens borrow their lexemes from here rather than from self
+        let source = self.source;
         let mut buffer_type = TokenType::Print;
-        let mut line_count = 1usize;
+        let mut line_count = self.first_line;
         let mut since_last_line = 0usize;
+        // (line, col) of the line comment's "//" (the shebang is one too), or of the outermost "/*";
+        // nested openers don't overwrite it
+        let mut comment_start = (line_count, 0usize);
+        // (line, col) of the opening quote of the string being scanned
+        let mut string_start = (0usize, 0usize);
+        // (line, col) of the 'e'/'E' starting a number's exponent
+        let mut exponent_start = (0usize, 0usize);
+        let mut interpolations: Vec<Interpolation> = Vec::new();
         
         // Maybe good impl. Needs refac, but don't know how to bcs too much overhead (too many arguments)
         // and too many custom functions needed.
-        for (i, c) in self.source.chars().enumerate() {
+        // byte index where the token being buffered (number, identifier, string) starts
+        let mut lexeme_start = 0usize;
+        // (line, column) where that token starts; for a string piece after "${...}" it's right after the '}'
+        let mut token_start = (0usize, 0usize);
+        // byte index where a string's text starts: after the opening quote, or after the '}' ending an interpolation
+        let mut text_start = 0usize;
+        // the string's text once an escape or a dropped '\r' made it differ from the source, borrowed otherwise
+        let mut owned_text: Option<String> = None;
+
+        // i counts characters (for columns), b is the byte index of c (for lexemes)
+        let mut chars = source.char_indices().enumerate().peekable();
+        // a character to be scanned again in the new state instead of pulling the next one
+        let mut replay = None;
+        // characters in the source, i.e. the i of the end of input
+        let mut char_count = 0usize;
+        while let Some((i, (b, c))) = replay.take().or_else(|| chars.next()) {
+            char_count = i + 1;
             match state {
+                ScannerState::Skip => {
+                    if is_resync_point(c) {
+                        replay = Some((i, (b, c)));
+                        state = ScannerState::Next;
+                        continue;
+                    }
+                }
                 ScannerState::BlockComment(nesting) => {
                     if c == '\n' {
                         line_count += 1;
@@ -240,238 +333,527 @@ impl Scanner {
                 }
                 ScannerState::Comment => {
                     if c == '\n' {
+                        if self.comments {
+                            let end = if source[..b].ends_with('\r') { b - 1 } else { b };
+                            let (line, col) = comment_start;
+                            self.tokens.push(Token::new(TokenType::Comment, Literal::Str(Cow::Borrowed(&source[lexeme_start + 2..end])), line, col, source, lexeme_start..end));
+                        }
                         line_count += 1;
                         since_last_line = i + 1;
                         state = ScannerState::Next;
                     }
                 }
                 ScannerState::SoloDot => {
-                    if c.is_numeric() {
-                        buffer_vec.clear();
-                        buffer_vec.push('.');
-                        buffer_vec.push(c);
+                    if c.is_ascii_digit() {
+                        lexeme_start = b - 1;
+                        token_start = (line_count, i - since_last_line - 1);
                         state = ScannerState::NumberWithDot;
                         continue;
                     }
-                    self.tokens.push(Token::new(TokenType::Dot, None, line_count, i - since_last_line - 1));
-                    // same as ScannerState::Next without is_numeric() check
-                    if c == '.' {
-                        state = ScannerState::SoloDot;
-                    } else if let Some(tt) = single_char.get(&c) {
-                        self.tokens.push(Token::new(tt.clone(), None, line_count, i - since_last_line));
-                    } else if let Some(tt) = first_two_char.get(&c) {
-                        state = ScannerState::MaybeTwo;
-                        buffer_type = tt.clone();
-                    } else if c.is_whitespace() {
-                        if c == '\n' {
-                            line_count += 1;
-                            since_last_line = i + 1;
-                        }
-                        state = ScannerState::Next;
-                    } else if c == '"' {
-                        state = ScannerState::InString;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
+                    self.tokens.push(Token::new(TokenType::Dot, Literal::None, line_count, i - since_last_line - 1, source, b - 1..b));
+                    replay = Some((i, (b, c)));
+                    state = ScannerState::Next;
+                    continue;
+                }
+                ScannerState::NumberExponent => {
+                    if c == '+' || c == '-' {
+                        state = ScannerState::NumberExponentSign;
+                    } else if c.is_ascii_digit() {
+                        state = ScannerState::NumberWithExponent;
                     } else {
-                        state = ScannerState::IdentifierOrKeyword;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
+                        let (line, col) = exponent_start;
+                        self.errors.push(ScanError::new(line, col, 1, "Expected digits after exponent".to_string()));
+                        replay = Some((i, (b, c)));
+                        state = ScannerState::Skip;
+                        continue;
+                    }
+                }
+                ScannerState::NumberExponentSign => {
+                    if c.is_ascii_digit() {
+                        state = ScannerState::NumberWithExponent;
+                    } else {
+                        let (line, col) = exponent_start;
+                        self.errors.push(ScanError::new(line, col, 1, "Expected digits after exponent".to_string()));
+                        replay = Some((i, (b, c)));
+                        state = ScannerState::Skip;
+                        continue;
                     }
-                    
                 }
-                ScannerState::NumberWithDot => {
+                ScannerState::NumberWithDot | ScannerState::NumberWithExponent => {
+                    if c == '_' {
+                        if let Err(error) = check_digit_separator(chars.peek().map(|(_, (_, next))| *next), line_count, i - since_last_line) {
+                            self.errors.push(error);
+                        }
+                        continue;
+                    }
+                    if (c == 'e' || c == 'E') && matches!(state, ScannerState::NumberWithDot) {
+                        exponent_start = (line_count, i - since_last_line);
+                        state = ScannerState::NumberExponent;
+                        continue;
+                    }
                     if c == '.' {
-                        println!("wtf");
-                        report_error(line_count, i - since_last_line, &self.source, "Did not expect '.'".to_string());
-                        process::exit(1);
-                    }
-                    if let Some(tt) = single_char.get(&c) {
-                        let number = buffer_vec.iter().collect::<String>();
-                        self.tokens.push(Token::new(TokenType::Number, Some(number), line_count, i - since_last_line - 1));
-                        self.tokens.push(Token::new(tt.clone(), None, line_count, i - since_last_line));
-                    } else if let Some(tt) = first_two_char.get(&c) {
-                        let number = buffer_vec.iter().collect::<String>();
-                        self.tokens.push(Token::new(TokenType::Number, Some(number), line_count, i - since_last_line - 1));
+                        if chars.peek().is_some_and(|(_, (_, next))| next.is_ascii_digit()) {
+                            self.errors.push(ScanError::new(line_count, i - since_last_line, 1, "Did not expect '.'".to_string()));
+                            state = ScannerState::Skip;
+                            continue;
+                        }
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
+                        self.tokens.push(Token::new(TokenType::Dot, Literal::None, line_count, i - since_last_line, source, b..b + 1));
+                        state = ScannerState::Next;
+                        continue;
+                    }
+                    if let Some(tt) = single_char(c) {
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
+                        self.tokens.push(Token::new(tt, Literal::None, line_count, i - since_last_line, source, b..b + 1));
+                        state = ScannerState::Next;
+                    } else if let Some(tt) = first_two_char(c) {
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
                         state = ScannerState::MaybeTwo;
-                        buffer_type = tt.clone();
+                        buffer_type = tt;
                     } else if c.is_whitespace() {
-                        let number = buffer_vec.iter().collect::<String>();
-                        self.tokens.push(Token::new(TokenType::Number, Some(number), line_count, i - since_last_line - 1));
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
                         if c == '\n' {
                             line_count += 1;
                             since_last_line = i + 1;
                         }
                         state = ScannerState::Next;
-                    } else if c.is_numeric() {
-                        buffer_vec.push(c);
                     }
                 }
                 ScannerState::Number => {
+                    if c == '_' {
+                        if let Err(error) = check_digit_separator(chars.peek().map(|(_, (_, next))| *next), line_count, i - since_last_line) {
+                            self.errors.push(error);
+                        }
+                        continue;
+                    }
+                    if c == 'e' || c == 'E' {
+                        exponent_start = (line_count, i - since_last_line);
+                        state = ScannerState::NumberExponent;
+                        continue;
+                    }
                     if c == '.' {
-                        buffer_vec.push(c);
-                        state = ScannerState::NumberWithDot;
+                        // only a digit after the dot makes it a fraction, otherwise it's a Dot token (`123.abs`)
+                        if chars.peek().is_some_and(|(_, (_, next))| next.is_ascii_digit()) {
+                            state = ScannerState::NumberWithDot;
+                            continue;
+                        }
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
+                        self.tokens.push(Token::new(TokenType::Dot, Literal::None, line_count, i - since_last_line, source, b..b + 1));
+                        state = ScannerState::Next;
                         continue;
                     }
-                    if let Some(tt) = single_char.get(&c) {
-                        let number = buffer_vec.iter().collect::<String>();
-                        self.tokens.push(Token::new(TokenType::Number, Some(number), line_count, i - since_last_line - 1));
-                        self.tokens.push(Token::new(tt.clone(), None, line_count, i - since_last_line));
-                    } else if let Some(tt) = first_two_char.get(&c) {
-                        let number = buffer_vec.iter().collect::<String>();
-                        self.tokens.push(Token::new(TokenType::Number, Some(number), line_count, i - since_last_line - 1));
+                    if let Some(tt) = single_char(c) {
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
+                        self.tokens.push(Token::new(tt, Literal::None, line_count, i - since_last_line, source, b..b + 1));
+                        state = ScannerState::Next;
+                    } else if let Some(tt) = first_two_char(c) {
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
                         state = ScannerState::MaybeTwo;
-                        buffer_type = tt.clone();
+                        buffer_type = tt;
                     } else if c.is_whitespace() {
-                        let number = buffer_vec.iter().collect::<String>();
-                        self.tokens.push(Token::new(TokenType::Number, Some(number), line_count, i - since_last_line - 1));
+                        match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                            Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                            Err(error) => self.errors.push(error),
+                        }
                         if c == '\n' {
                             line_count += 1;
                             since_last_line = i + 1;
                         }
                         state = ScannerState::Next;
-                    } else {
-                        buffer_vec.push(c);
                     }
                 }
                 ScannerState::InString => {
-                    buffer_vec.push(c);
+                    // CRLF line endings inside a multi-line string are stored as plain '\n'
+                    if c == '\r' {
+                        owned_text.get_or_insert_with(|| source[text_start..b].to_string());
+                        continue;
+                    }
+                    if c == '\\' {
+                        owned_text.get_or_insert_with(|| source[text_start..b].to_string());
+                        state = ScannerState::InStringEscape;
+                        continue;
+                    }
+                    if c == '"' {
+                        let text = owned_text.take().map_or(Cow::Borrowed(&source[text_start..b]), Cow::Owned);
+                        let (line, col) = token_start;
+                        self.tokens.push(Token::new(TokenType::String, Literal::Str(text), line, col, source, lexeme_start..b + 1));
+                        state = ScannerState::Next;
+                        continue;
+                    }
+                    // "a ${b} c" scans as String("a "), InterpStart, b, InterpEnd, String(" c"); fragments may be empty
+                    if c == '$' && chars.peek().is_some_and(|(_, (_, next))| *next == '{') {
+                        chars.next();
+                        let text = owned_text.take().map_or(Cow::Borrowed(&source[text_start..b]), Cow::Owned);
+                        let (line, col) = token_start;
+                        self.tokens.push(Token::new(TokenType::String, Literal::Str(text), line, col, source, lexeme_start..b));
+                        self.tokens.push(Token::new(TokenType::InterpStart, Literal::None, line_count, i - since_last_line, source, b..b + 2));
+                        interpolations.push(Interpolation {
+                            braces: 0,
+                            start: (line_count, i - since_last_line),
+                            string_start,
+                        });
+                        state = ScannerState::Next;
+                        continue;
+                    }
+                    if let Some(text) = &mut owned_text {
+                        text.push(c);
+                    }
+                    if c == '\n' {
+                        line_count += 1;
+                        since_last_line = i + 1;
+                    }
+                }
+                ScannerState::InRawString => {
+                    if c == '\r' {
+                        owned_text.get_or_insert_with(|| source[text_start..b].to_string());
+                        continue;
+                    }
                     if c == '"' {
-                        let word = buffer_vec.iter().collect::<String>();
-                        self.tokens.push(Token::new(TokenType::String, Some(word), line_count, i - since_last_line));
+                        let text = owned_text.take().map_or(Cow::Borrowed(&source[text_start..b]), Cow::Owned);
+                        let (line, col) = token_start;
+                        self.tokens.push(Token::new(TokenType::String, Literal::Str(text), line, col, source, lexeme_start..b + 1));
                         state = ScannerState::Next;
+                        continue;
+                    }
+                    if let Some(text) = &mut owned_text {
+                        text.push(c);
                     }
+                    if c == '\n' {
+                        line_count += 1;
+                        since_last_line = i + 1;
+                    }
+                }
+                ScannerState::InStringEscape => {
+                    let escaped = match c {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '$' => '$',
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        _ => {
+                            // the string itself is fine, keep scanning it without the bad escape
+                            self.errors.push(ScanError::new(line_count, i - since_last_line - 1, 1, format!("Invalid escape sequence '\\{}'", c)));
+                            if c == '\n' {
+                                line_count += 1;
+                                since_last_line = i + 1;
+                            }
+                            state = ScannerState::InString;
+                            continue;
+                        }
+                    };
+                    if let Some(text) = &mut owned_text {
+                        text.push(escaped);
+                    }
+                    state = ScannerState::InString;
                 }
                 ScannerState::Next => {
                     if c == '.' {
                         state = ScannerState::SoloDot;
-                    } else if let Some(tt) = single_char.get(&c) {
-                        self.tokens.push(Token::new(tt.clone(), None, line_count, i - since_last_line));
-                    } else if let Some(tt) = first_two_char.get(&c) {
+                    } else if let Some(tt) = single_char(c) {
+                        self.tokens.push(Token::new(tt, Literal::None, line_count, i - since_last_line, source, b..b + 1));
+                    } else if let Some(tt) = first_two_char(c) {
                         state = ScannerState::MaybeTwo;
-                        buffer_type = tt.clone();
+                        buffer_type = tt;
                     } else if c.is_whitespace() {
                         if c == '\n' {
                             line_count += 1;
                             since_last_line = i + 1;
                         }
                     } else if c == '"' {
+                        string_start = (line_count, i - since_last_line);
+                        token_start = string_start;
                         state = ScannerState::InString;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
-                    } else if c.is_numeric() {
+                        lexeme_start = b;
+                        text_start = b + 1;
+                    } else if c.is_ascii_digit() {
                         state = ScannerState::Number;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
-                    } else {
+                        lexeme_start = b;
+                        token_start = (line_count, i - since_last_line);
+                    } else if c.is_alphabetic() || c == '_' {
                         state = ScannerState::IdentifierOrKeyword;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
+                        lexeme_start = b;
+                        token_start = (line_count, i - since_last_line);
+                    } else {
+                        self.errors.push(ScanError::new(line_count, i - since_last_line, 1, format!("Unexpected character '{}'", c)));
+                        state = ScannerState::Next;
                     }
                 }
                 ScannerState::MaybeTwo => {
-                    if let Some(tt) = single_char.get(&c) {
-                        if c == '*' && buffer_type == TokenType::Slash {
-                            state = ScannerState::BlockComment(1);
-                            continue;
-                        }
-                        self.tokens.push(Token::new(buffer_type, None, line_count, i - since_last_line - 1));
-                        self.tokens.push(Token::new(tt.clone(), None, line_count, i - since_last_line));
+                    // maximal munch from the left: `a-->b` is MinusMinus then Greater, never Minus then Arrow
+                    let two_char = match (buffer_type, c) {
+                        (TokenType::Bang, '=') => Some(TokenType::BangEqual),
+                        (TokenType::Equal, '=') => Some(TokenType::EqualEqual),
+                        (TokenType::Greater, '=') => Some(TokenType::GreaterEqual),
+                        (TokenType::Less, '=') => Some(TokenType::LessEqual),
+                        (TokenType::Greater, '>') => Some(TokenType::GreaterGreater),
+                        (TokenType::Less, '<') => Some(TokenType::LessLess),
+                        (TokenType::Plus, '+') => Some(TokenType::PlusPlus),
+                        (TokenType::Minus, '-') => Some(TokenType::MinusMinus),
+                        (TokenType::Minus, '>') => Some(TokenType::Arrow),
+                        _ => None,
+                    };
+                    if let Some(tt) = two_char {
+                        self.tokens.push(Token::new(tt, Literal::None, line_count, i - since_last_line - 1, source, b - 1..b + 1));
                         state = ScannerState::Next;
-                    } else if let Some(tt) = first_two_char.get(&c) {
-                        if c == '=' && buffer_type != TokenType::Slash {
-                            let tt = match buffer_type {
-                                TokenType::Bang => TokenType::BangEqual,
-                                TokenType::Equal => TokenType::EqualEqual,
-                                TokenType::Greater => TokenType::GreaterEqual,
-                                TokenType::Less => TokenType::LessEqual,
-                                _ => {
-                                    println!("Somehow a not possible two character token was considered as possible two character token");
-                                    process::exit(1);
-                                }
-                            };
-                            self.tokens.push(Token::new(tt, None, line_count, i - since_last_line));
-                        } else if c == '/' && buffer_type == TokenType::Slash {
-                            state = ScannerState::Comment;
-                        }
-                        else {
-                            self.tokens.push(Token::new(buffer_type, None, line_count, i - since_last_line - 1));
-                            buffer_type = tt.clone();
-                        }
-                    } else if c.is_whitespace() {
-                        self.tokens.push(Token::new(buffer_type, None, line_count, i - since_last_line - 1));
-                        if c == '\n' {
-                            line_count += 1;
-                            since_last_line = i + 1;
-                        }
-                        state = ScannerState::Next;
-                    } else if c == '"' { 
-                        self.tokens.push(Token::new(buffer_type, None, line_count, i - since_last_line - 1));
-                        state = ScannerState::InString;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
-                    } else if c.is_numeric() {
-                        self.tokens.push(Token::new(buffer_type, None, line_count, i - since_last_line - 1));
-                        state = ScannerState::Number;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
+                    } else if buffer_type == TokenType::Slash && c == '/' {
+                        comment_start = (line_count, i - since_last_line - 1);
+                        lexeme_start = b - 1;
+                        state = ScannerState::Comment;
+                    } else if buffer_type == TokenType::Slash && c == '*' {
+                        comment_start = (line_count, i - since_last_line - 1);
+                        state = ScannerState::BlockComment(1);
                     } else {
-                        self.tokens.push(Token::new(buffer_type, None, line_count, i - since_last_line - 1));
-                        state = ScannerState::IdentifierOrKeyword;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
+                        // a lone operator (`/=` is Slash then Equal), whatever follows it starts the next token
+                        self.tokens.push(Token::new(buffer_type, Literal::None, line_count, i - since_last_line - 1, source, b - 1..b));
+                        replay = Some((i, (b, c)));
+                        state = ScannerState::Next;
+                        continue;
                     }
                 }
                 ScannerState::IdentifierOrKeyword => {
-                    if let Some(tt) = single_char.get(&c) {
-                        let word = buffer_vec.iter().collect::<String>();
-                        if let Some(word_tt) = keywords.get(&word) {
-                            self.tokens.push(Token::new(*word_tt, None, line_count, i - since_last_line - 1));
-                        } else {
-                            self.tokens.push(Token::new(TokenType::Identifier, Some(word), line_count, i - since_last_line - 1));
-                        }
-                        self.tokens.push(Token::new(tt.clone(), None, line_count, i - since_last_line));
-                        state = ScannerState::Next;
-                    } else if let Some(tt) = first_two_char.get(&c) {
-                        let word = buffer_vec.iter().collect::<String>();
-                        if let Some(word_tt) = keywords.get(&word) {
-                            self.tokens.push(Token::new(*word_tt, None, line_count, i - since_last_line - 1));
-                        } else {
-                            self.tokens.push(Token::new(TokenType::Identifier, Some(word), line_count, i - since_last_line - 1));
-                        }
-                        state = ScannerState::MaybeTwo;
-                        buffer_type = tt.clone();
-                    } else if c.is_whitespace() {
-                        let word = buffer_vec.iter().collect::<String>();
-                        if let Some(word_tt) = keywords.get(&word) {
-                            self.tokens.push(Token::new(*word_tt, None, line_count, i - since_last_line - 1));
-                        } else {
-                            self.tokens.push(Token::new(TokenType::Identifier, Some(word), line_count, i - since_last_line - 1));
-                        }
-                        state = ScannerState::Next;
-                        if c == '\n' {
-                            line_count += 1;
-                            since_last_line = i + 1;
-                        }
-                    }  else if c == '"' { 
-                        let word = buffer_vec.iter().collect::<String>();
-                        if let Some(word_tt) = keywords.get(&word) {
-                            self.tokens.push(Token::new(*word_tt, None, line_count, i - since_last_line - 1));
-                        } else {
-                            self.tokens.push(Token::new(TokenType::Identifier, Some(word), line_count, i - since_last_line - 1));
-                        }
-                        state = ScannerState::InString;
-                        buffer_vec.clear();
-                        buffer_vec.push(c);
+                    if c.is_alphanumeric() || c == '_' {
+                        continue;
+                    }
+                    if c == '"' && &source[lexeme_start..b] == "r" {
+                        // r"..." keeps backslashes verbatim, the token starts at the 'r'
+                        string_start = (line_count, i - since_last_line - 1);
+                        token_start = string_start;
+                        state = ScannerState::InRawString;
+                        lexeme_start = b - 1;
+                        text_start = b + 1;
+                        continue;
+                    }
+                    let word = &source[lexeme_start..b];
+                    if let Some(word_tt) = keyword(word) {
+                        self.tokens.push(Token::new(word_tt, keyword_literal(word_tt), token_start.0, token_start.1, source, lexeme_start..b));
                     } else {
-                        buffer_vec.push(c);
+                        self.tokens.push(Token::new(TokenType::Identifier, Literal::Symbol(self.interner.intern(word)), token_start.0, token_start.1, source, lexeme_start..b));
                     }
+                    // whatever ended the identifier starts the next token (or is reported as unexpected there)
+                    replay = Some((i, (b, c)));
+                    state = ScannerState::Next;
+                    continue;
+                }
+            }
+
+            // a brace that was just emitted as a token (not one inside a string or comment) leaves us in Next
+            if let (Some(interpolation), ScannerState::Next) = (interpolations.last_mut(), &state) {
+                if c == '{' {
+                    interpolation.braces += 1;
+                } else if c == '}' && interpolation.braces > 0 {
+                    interpolation.braces -= 1;
+                } else if c == '}' {
+                    self.tokens.pop();
+                    self.tokens.push(Token::new(TokenType::InterpEnd, Literal::None, line_count, i - since_last_line, source, b..b + 1));
+                    string_start = interpolation.string_start;
+                    interpolations.pop();
+                    state = ScannerState::InString;
+                    lexeme_start = b + 1;
+                    text_start = b + 1;
+                    token_start = (line_count, i - since_last_line + 1);
                 }
             }
         }
 
-        let eof_token = Token::new(TokenType::Eof, None, line_count, 0);
+        // the end of input finishes the pending token just like whitespace would
+        let (i, b) = (char_count, source.len());
+        match state {
+            ScannerState::MaybeTwo => {
+                self.tokens.push(Token::new(buffer_type, Literal::None, line_count, i - since_last_line - 1, source, b - 1..b));
+            }
+            ScannerState::SoloDot => {
+                self.tokens.push(Token::new(TokenType::Dot, Literal::None, line_count, i - since_last_line - 1, source, b - 1..b));
+            }
+            ScannerState::Number | ScannerState::NumberWithDot | ScannerState::NumberWithExponent => {
+                match parse_number(&source[lexeme_start..b], line_count, i - since_last_line) {
+                    Ok(number) => self.tokens.push(Token::new(TokenType::Number, number, token_start.0, token_start.1, source, lexeme_start..b)),
+                    Err(error) => self.errors.push(error),
+                }
+            }
+            ScannerState::NumberExponent | ScannerState::NumberExponentSign => {
+                let (line, col) = exponent_start;
+                self.errors.push(ScanError::new(line, col, 1, "Expected digits after exponent".to_string()));
+            }
+            ScannerState::IdentifierOrKeyword => {
+                let word = &source[lexeme_start..b];
+                if let Some(word_tt) = keyword(word) {
+                    self.tokens.push(Token::new(word_tt, keyword_literal(word_tt), token_start.0, token_start.1, source, lexeme_start..b));
+                } else {
+                    self.tokens.push(Token::new(TokenType::Identifier, Literal::Symbol(self.interner.intern(word)), token_start.0, token_start.1, source, lexeme_start..b));
+                }
+            }
+            // a line comment (or shebang) may run up to the end of input without a newline
+            ScannerState::Comment if self.comments => {
+                let (line, col) = comment_start;
+                self.tokens.push(Token::new(TokenType::Comment, Literal::Str(Cow::Borrowed(&source[lexeme_start + 2..b])), line, col, source, lexeme_start..b));
+            }
+            _ => {}
+        }
+
+        let complete = interpolations.is_empty()
+            && !matches!(
+                state,
+                ScannerState::BlockComment(_)
+                    | ScannerState::BlockCommentMaybeAdd(_)
+                    | ScannerState::BlockCommentMaybeSubtract(_)
+                    | ScannerState::InString
+                    | ScannerState::InStringEscape
+                    | ScannerState::InRawString
+            );
+
+        if let ScannerState::BlockComment(_)
+            | ScannerState::BlockCommentMaybeAdd(_)
+            | ScannerState::BlockCommentMaybeSubtract(_) = state
+        {
+            let (line, col) = comment_start;
+            self.errors.push(ScanError::new(line, col, 1, "Unterminated block comment".to_string()));
+        }
+
+        if let ScannerState::InString | ScannerState::InStringEscape | ScannerState::InRawString = state {
+            let (line, col) = string_start;
+            self.errors.push(ScanError::new(line, col, 1, "Unterminated string".to_string()));
+        }
+
+        if let Some(interpolation) = interpolations.last() {
+            let (line, col) = interpolation.start;
+            self.errors.push(ScanError::new(line, col, 1, "Unterminated interpolation".to_string()));
+        }
+
+        // Eof sits right after the last character, so "expected ... at end" errors point past the code
+        let eof_token = Token::new(TokenType::Eof, Literal::None, line_count, i - since_last_line, source, b..b);
 
         self.tokens.push(eof_token);
-        println!("{:#?}", self.tokens);
+
+        complete
     }
 }
+
+// after a lexical error scanning resumes at whitespace or at a character that starts a token;
+// a '.' is not one, so the rest of `1.2.3` is dropped as a whole
+fn is_resync_point(c: char) -> bool {
+    c != '.' && (c.is_whitespace() || c == '"' || single_char(c).is_some() || first_two_char(c).is_some())
+}
+
+// `end_col` is the column right after the number, `lexeme` its source text (underscores included)
+fn parse_number(lexeme: &str, line: usize, end_col: usize) -> Result<Literal<'static>, ScanError> {
+    // separators were checked while scanning, the text is only copied when there are some to strip
+    let text = if lexeme.contains('_') {
+        Cow::Owned(lexeme.replace('_', ""))
+    } else {
+        Cow::Borrowed(lexeme)
+    };
+    match f64::from_str(&text) {
+        Ok(number) => Ok(Literal::Number(number)),
+        Err(_) => {
+            let len = lexeme.chars().count();
+            Err(ScanError::new(line, end_col - len, len, format!("Invalid number '{}'", lexeme)))
+        }
+    }
+}
+
+// `_` may only separate two digits, e.g. `1_000` or `3.141_592`; the caller strips it from the buffer.
+fn check_digit_separator(next: Option<char>, line: usize, col: usize) -> Result<(), ScanError> {
+    match next {
+        Some('_') => Err(ScanError::new(line, col + 1, 1, "Doubled '_' in number".to_string())),
+        Some(next) if next.is_ascii_digit() => Ok(()),
+        _ => Err(ScanError::new(line, col, 1, "Trailing '_' in number".to_string())),
+    }
+}
+
+fn single_char(c: char) -> Option<TokenType> {
+    match c {
+        '(' => Some(TokenType::LeftParen),
+        ')' => Some(TokenType::RightParen),
+        '{' => Some(TokenType::LeftBrace),
+        '}' => Some(TokenType::RightBrace),
+        '[' => Some(TokenType::LeftBracket),
+        ']' => Some(TokenType::RightBracket),
+        ',' => Some(TokenType::Comma),
+        '.' => Some(TokenType::Dot),
+        ';' => Some(TokenType::Semicolon),
+        '*' => Some(TokenType::Star),
+        '%' => Some(TokenType::Percent),
+        '?' => Some(TokenType::Question),
+        ':' => Some(TokenType::Colon),
+        '&' => Some(TokenType::Amp),
+        '|' => Some(TokenType::Pipe),
+        '^' => Some(TokenType::Caret),
+        _ => None,
+    }
+}
+
+// characters that may start a two character token (or a comment, for '/')
+fn first_two_char(c: char) -> Option<TokenType> {
+    match c {
+        '!' => Some(TokenType::Bang),
+        '+' => Some(TokenType::Plus),
+        '-' => Some(TokenType::Minus),
+        '=' => Some(TokenType::Equal),
+        '>' => Some(TokenType::Greater),
+        '<' => Some(TokenType::Less),
+        '/' => Some(TokenType::Slash),
+        _ => None,
+    }
+}
+
+fn keyword(word: &str) -> Option<TokenType> {
+    match word {
+        "and"      => Some(TokenType::And),
+        "break"    => Some(TokenType::Break),
+        "case"     => Some(TokenType::Case),
+        "class"    => Some(TokenType::Class),
+        "continue" => Some(TokenType::Continue),
+        "default"  => Some(TokenType::Default),
+        "else"     => Some(TokenType::Else),
+        "false"    => Some(TokenType::False),
+        "fun"      => Some(TokenType::Fun),
+        "for"      => Some(TokenType::For),
+        "if"       => Some(TokenType::If),
+        "nil"      => Some(TokenType::Nil),
+        "or"       => Some(TokenType::Or),
+        "print"    => Some(TokenType::Print),
+        "return"   => Some(TokenType::Return),
+        "super"    => Some(TokenType::Super),
+        "switch"   => Some(TokenType::Switch),
+        "this"     => Some(TokenType::This),
+        "true"     => Some(TokenType::True),
+        "var"      => Some(TokenType::Var),
+        "while"    => Some(TokenType::While),
+        _ => None,
+    }
+}
+
+// `true`, `false` and `nil` carry their value, the other keywords nothing
+fn keyword_literal(ttype: TokenType) -> Literal<'static> {
+    match ttype {
+        TokenType::True => Literal::Bool(true),
+        TokenType::False => Literal::Bool(false),
+        TokenType::Nil => Literal::Nil,
+        _ => Literal::None,
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,827 @@
+use std::borrow::Cow;
+
+use super::Scanner;
+use crate::lox::tokens::{render, Literal, Token, TokenType};
+
+// `tok!(Number "3.14" @ 1:4)` is the token a scan should produce for that lexeme at line 1, column 4. The
+// literal is worked out from the lexeme (see `literal`), or given last when that can't be done:
+// `tok!(String "\"a\\n\"" @ 1:0, Literal::Str("a\n".into()))`.
+macro_rules! tok {
+    ($ttype:ident $lexeme:literal @ $line:literal : $col:literal) => {
+        tok!($ttype $lexeme @ $line:$col, literal(TokenType::$ttype, $lexeme))
+    };
+    ($ttype:ident $lexeme:literal @ $line:literal : $col:literal, $literal:expr) => {
+        Token::new(TokenType::$ttype, $literal, $line, $col, $lexeme, 0..$lexeme.len())
+    };
+}
+
+// the literal a scanned `lexeme` carries, when it can be told from the lexeme alone (no escapes)
+fn literal(ttype: TokenType, lexeme: &str) -> Literal<'_> {
+    match ttype {
+        TokenType::Number => Literal::Number(lexeme.replace('_', "").parse().unwrap()),
+        TokenType::String => Literal::Str(Cow::Borrowed(lexeme.strip_prefix('r').unwrap_or(lexeme).trim_matches('"'))),
+        TokenType::Comment => Literal::Str(Cow::Borrowed(&lexeme[2..])),
+        TokenType::True => Literal::Bool(true),
+        TokenType::False => Literal::Bool(false),
+        TokenType::Nil => Literal::Nil,
+        _ => Literal::None,
+    }
+}
+
+// The tokens of `source`, comments included, which must scan without errors. Identifiers' symbols depend
+// on the order names were first seen in, so they're dropped here and checked on their own.
+fn scan(source: &str) -> Vec<Token<'_>> {
+    let mut scanner = Scanner::new(source);
+    scanner.comments = true;
+    let tokens = match scanner.scan_tokens() {
+        Ok(tokens) => tokens.to_vec(),
+        Err(errors) => panic!("{:?} scanned with errors {:?}", source, errors),
+    };
+    tokens.into_iter().map(|token| without_symbol(token, source)).collect()
+}
+
+fn without_symbol<'src>(token: Token<'src>, source: &'src str) -> Token<'src> {
+    if token.ttype() != TokenType::Identifier {
+        return token;
+    }
+    let span = token.span();
+    Token::new(TokenType::Identifier, Literal::None, span.line, span.col, source, token.bytes())
+}
+
+// The errors scanning `source` as `line:col message`, and the tokens scanned around them.
+fn scan_errors(source: &str) -> (Vec<String>, Vec<Token<'_>>) {
+    let mut scanner = Scanner::new(source);
+    let errors = match scanner.scan_tokens() {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors.iter().map(|error| format!("{}:{} {}", error.span.line, error.span.col, error.message)).collect(),
+    };
+    let tokens = std::mem::take(&mut scanner.tokens).into_iter().map(|token| without_symbol(token, source)).collect();
+    (errors, tokens)
+}
+
+fn types(source: &str) -> Vec<TokenType> {
+    scan(source).iter().map(Token::ttype).collect()
+}
+
+#[test]
+fn tok_builds_the_scanned_token() {
+    assert_eq!(scan("  3.14"), vec![tok!(Number "3.14" @ 1:2), tok!(Eof "" @ 1:6)]);
+}
+
+// Next
+
+#[test]
+fn next_emits_single_character_tokens() {
+    use TokenType::*;
+    assert_eq!(
+        types("(){}[],;*%?:&|^"),
+        [LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket, Comma, Semicolon, Star, Percent, Question, Colon, Amp, Pipe, Caret, Eof]
+    );
+}
+
+#[test]
+fn next_skips_whitespace_and_counts_lines() {
+    assert_eq!(
+        scan(" ( \t)\n\n  ;\r\n,"),
+        vec![tok!(LeftParen "(" @ 1:1), tok!(RightParen ")" @ 1:4), tok!(Semicolon ";" @ 3:2), tok!(Comma "," @ 4:0), tok!(Eof "" @ 4:1)]
+    );
+}
+
+#[test]
+fn next_reports_unexpected_characters_and_carries_on() {
+    let (errors, tokens) = scan_errors("(#)");
+    assert_eq!(errors, ["1:1 Unexpected character '#'"]);
+    assert_eq!(tokens, vec![tok!(LeftParen "(" @ 1:0), tok!(RightParen ")" @ 1:2), tok!(Eof "" @ 1:3)]);
+}
+
+// SoloDot
+
+#[test]
+fn solo_dot_before_a_digit_starts_a_number() {
+    assert_eq!(scan(".5"), vec![tok!(Number ".5" @ 1:0), tok!(Eof "" @ 1:2)]);
+}
+
+#[test]
+fn solo_dot_before_anything_else_is_a_dot() {
+    assert_eq!(
+        scan("a.b ."),
+        vec![tok!(Identifier "a" @ 1:0), tok!(Dot "." @ 1:1), tok!(Identifier "b" @ 1:2), tok!(Dot "." @ 1:4), tok!(Eof "" @ 1:5)]
+    );
+}
+
+// MaybeTwo
+
+#[test]
+fn maybe_two_forms_two_character_tokens() {
+    use TokenType::*;
+    assert_eq!(
+        types("!= == >= <= >> << ++ -- ->"),
+        [BangEqual, EqualEqual, GreaterEqual, LessEqual, GreaterGreater, LessLess, PlusPlus, MinusMinus, Arrow, Eof]
+    );
+}
+
+#[test]
+fn maybe_two_falls_back_to_one_character_and_rescans_the_next() {
+    assert_eq!(
+        scan("!a=1"),
+        vec![tok!(Bang "!" @ 1:0), tok!(Identifier "a" @ 1:1), tok!(Equal "=" @ 1:2), tok!(Number "1" @ 1:3), tok!(Eof "" @ 1:4)]
+    );
+    assert_eq!(scan("<"), vec![tok!(Less "<" @ 1:0), tok!(Eof "" @ 1:1)]);
+}
+
+// Comment
+
+#[test]
+fn comment_runs_to_the_end_of_the_line() {
+    assert_eq!(
+        scan("a // b c\nd"),
+        vec![tok!(Identifier "a" @ 1:0), tok!(Comment "// b c" @ 1:2), tok!(Identifier "d" @ 2:0), tok!(Eof "" @ 2:1)]
+    );
+}
+
+#[test]
+fn comments_are_dropped_unless_asked_for() {
+    let mut scanner = Scanner::new("// a\n1");
+    let tokens = scanner.scan_tokens().unwrap();
+    assert_eq!(tokens, [tok!(Number "1" @ 2:0), tok!(Eof "" @ 2:1)]);
+}
+
+#[test]
+fn shebang_is_a_comment() {
+    assert_eq!(scan("#!/usr/bin/env lox\n1"), vec![tok!(Comment "#!/usr/bin/env lox" @ 1:0), tok!(Number "1" @ 2:0), tok!(Eof "" @ 2:1)]);
+}
+
+// BlockComment, BlockCommentMaybeAdd, BlockCommentMaybeSubtract
+
+#[test]
+fn block_comment_is_skipped_and_counts_lines() {
+    assert_eq!(scan("/* a\nb */ 1"), vec![tok!(Number "1" @ 2:5), tok!(Eof "" @ 2:6)]);
+}
+
+#[test]
+fn block_comment_maybe_add_opens_a_nested_comment() {
+    assert_eq!(scan("/* a /* b */ c */ 1"), vec![tok!(Number "1" @ 1:18), tok!(Eof "" @ 1:19)]);
+    // more slashes keep waiting for the '*'
+    assert_eq!(scan("/* //* */ */ 1"), vec![tok!(Number "1" @ 1:13), tok!(Eof "" @ 1:14)]);
+    // anything else goes back into the comment
+    assert_eq!(scan("/* /a */ 1"), vec![tok!(Number "1" @ 1:9), tok!(Eof "" @ 1:10)]);
+}
+
+#[test]
+fn block_comment_maybe_subtract_closes_one_level() {
+    assert_eq!(scan("/* ** */ 1"), vec![tok!(Number "1" @ 1:9), tok!(Eof "" @ 1:10)]);
+    assert_eq!(scan("/* *a */ 1"), vec![tok!(Number "1" @ 1:9), tok!(Eof "" @ 1:10)]);
+    assert_eq!(scan("/* *\n*/ 1"), vec![tok!(Number "1" @ 2:3), tok!(Eof "" @ 2:4)]);
+}
+
+// IdentifierOrKeyword
+
+#[test]
+fn identifier_runs_over_letters_digits_and_underscores() {
+    assert_eq!(
+        scan("_a1 b_2+c"),
+        vec![
+            tok!(Identifier "_a1" @ 1:0),
+            tok!(Identifier "b_2" @ 1:4),
+            tok!(Plus "+" @ 1:7),
+            tok!(Identifier "c" @ 1:8),
+            tok!(Eof "" @ 1:9)
+        ]
+    );
+}
+
+#[test]
+fn keywords_and_their_literals() {
+    use TokenType::*;
+    assert_eq!(
+        types("and break case class continue default else false fun for if nil or print return super switch this true var while"),
+        [And, Break, Case, Class, Continue, Default, Else, False, Fun, For, If, Nil, Or, Print, Return, Super, Switch, This, True, Var, While, Eof]
+    );
+    assert_eq!(
+        scan("true false nil"),
+        vec![tok!(True "true" @ 1:0), tok!(False "false" @ 1:5), tok!(Nil "nil" @ 1:11), tok!(Eof "" @ 1:14)]
+    );
+}
+
+#[test]
+fn identifiers_get_one_symbol_per_name() {
+    let mut scanner = Scanner::new("a b a");
+    let symbols: Vec<_> = scanner.scan_tokens().unwrap().iter().map(|token| token.literal().clone()).collect();
+    assert_eq!(symbols[0], symbols[2]);
+    assert_ne!(symbols[0], symbols[1]);
+    let Literal::Symbol(b) = symbols[1] else { panic!("{:?}", symbols) };
+    assert_eq!(scanner.interner().resolve(b), "b");
+}
+
+// InString, InStringEscape
+
+#[test]
+fn string_literal_is_the_text_between_the_quotes() {
+    assert_eq!(scan("x\"a b\";"), vec![tok!(Identifier "x" @ 1:0), tok!(String "\"a b\"" @ 1:1), tok!(Semicolon ";" @ 1:6), tok!(Eof "" @ 1:7)]);
+}
+
+#[test]
+fn string_runs_over_lines() {
+    assert_eq!(scan("\"a\nb\" 1"), vec![tok!(String "\"a\nb\"" @ 1:0), tok!(Number "1" @ 2:3), tok!(Eof "" @ 2:4)]);
+    assert_eq!(scan("\"a\r\nb\"")[0].literal(), &Literal::Str("a\nb".into()));
+}
+
+#[test]
+fn string_escapes() {
+    assert_eq!(scan(r#""\"\\\$\n\t\r""#)[0].literal(), &Literal::Str("\"\\$\n\t\r".into()));
+}
+
+#[test]
+fn string_invalid_escape_is_reported_and_the_string_kept() {
+    let (errors, tokens) = scan_errors(r#""a\qb""#);
+    assert_eq!(errors, ["1:2 Invalid escape sequence '\\q'"]);
+    assert_eq!(tokens[0].literal(), &Literal::Str("ab".into()));
+}
+
+#[test]
+fn string_interpolation_switches_to_tokens_and_back() {
+    assert_eq!(
+        scan(r#""a ${b} c""#),
+        vec![
+            tok!(String "\"a " @ 1:0),
+            tok!(InterpStart "${" @ 1:3),
+            tok!(Identifier "b" @ 1:5),
+            tok!(InterpEnd "}" @ 1:6),
+            tok!(String " c\"" @ 1:7),
+            tok!(Eof "" @ 1:10)
+        ]
+    );
+}
+
+// InRawString
+
+#[test]
+fn raw_string_keeps_backslashes() {
+    assert_eq!(scan(r#"r"a\n" r"#), vec![tok!(String "r\"a\\n\"" @ 1:0), tok!(Identifier "r" @ 1:7), tok!(Eof "" @ 1:8)]);
+}
+
+// Number, NumberWithDot
+
+#[test]
+fn number_ends_at_anything_but_a_digit() {
+    assert_eq!(
+        scan("12+3 4\n5"),
+        vec![tok!(Number "12" @ 1:0), tok!(Plus "+" @ 1:2), tok!(Number "3" @ 1:3), tok!(Number "4" @ 1:5), tok!(Number "5" @ 2:0), tok!(Eof "" @ 2:1)]
+    );
+    assert_eq!(scan("1=="), vec![tok!(Number "1" @ 1:0), tok!(EqualEqual "==" @ 1:1), tok!(Eof "" @ 1:3)]);
+}
+
+#[test]
+fn number_dot_needs_a_digit_after_it() {
+    assert_eq!(scan("3.14;"), vec![tok!(Number "3.14" @ 1:0), tok!(Semicolon ";" @ 1:4), tok!(Eof "" @ 1:5)]);
+    assert_eq!(
+        scan("123.abs"),
+        vec![tok!(Number "123" @ 1:0), tok!(Dot "." @ 1:3), tok!(Identifier "abs" @ 1:4), tok!(Eof "" @ 1:7)]
+    );
+}
+
+#[test]
+fn number_with_dot_ends_at_a_second_dot() {
+    assert_eq!(scan("1.5.a"), vec![tok!(Number "1.5" @ 1:0), tok!(Dot "." @ 1:3), tok!(Identifier "a" @ 1:4), tok!(Eof "" @ 1:5)]);
+    let (errors, _) = scan_errors("1.2.3");
+    assert_eq!(errors, ["1:3 Did not expect '.'"]);
+}
+
+#[test]
+fn number_digit_separators() {
+    assert_eq!(scan("1_000.5_0"), vec![tok!(Number "1_000.5_0" @ 1:0), tok!(Eof "" @ 1:9)]);
+    assert_eq!(scan_errors("1__0").0, ["1:2 Doubled '_' in number"]);
+    assert_eq!(scan_errors("1_ ").0, ["1:1 Trailing '_' in number"]);
+}
+
+// NumberExponent, NumberExponentSign, NumberWithExponent
+
+#[test]
+fn number_exponent() {
+    assert_eq!(
+        scan("1e10 2.5E-3 4e+2"),
+        vec![tok!(Number "1e10" @ 1:0), tok!(Number "2.5E-3" @ 1:5), tok!(Number "4e+2" @ 1:12), tok!(Eof "" @ 1:16)]
+    );
+}
+
+#[test]
+fn number_exponent_needs_digits() {
+    assert_eq!(scan_errors("1e;").0, ["1:1 Expected digits after exponent"]);
+    assert_eq!(scan_errors("1e+;").0, ["1:1 Expected digits after exponent"]);
+}
+
+// Skip
+
+#[test]
+fn skip_drops_the_rest_of_a_bad_lexeme() {
+    let (errors, tokens) = scan_errors("1ex.y + 2");
+    assert_eq!(errors, ["1:1 Expected digits after exponent"]);
+    assert_eq!(tokens, vec![tok!(Plus "+" @ 1:6), tok!(Number "2" @ 1:8), tok!(Eof "" @ 1:9)]);
+}
+
+// nested block comments
+
+#[test]
+fn block_comments_nest_two_levels() {
+    assert_eq!(scan("/* a /* b */ still a comment */ 1"), vec![tok!(Number "1" @ 1:32), tok!(Eof "" @ 1:33)]);
+}
+
+#[test]
+fn block_comments_nest_three_levels() {
+    assert_eq!(scan("/* a /* b /* c */ b */ a */ 1"), vec![tok!(Number "1" @ 1:28), tok!(Eof "" @ 1:29)]);
+    assert_eq!(scan("/*/**/*/ 1"), vec![tok!(Number "1" @ 1:9), tok!(Eof "" @ 1:10)]);
+}
+
+#[test]
+fn quotes_inside_a_block_comment_dont_hide_its_end() {
+    // strings aren't recognized inside comments, the first "*/" closes it
+    assert_eq!(scan("/* \"*/ 1"), vec![tok!(Number "1" @ 1:7), tok!(Eof "" @ 1:8)]);
+    let (errors, _) = scan_errors("/* \"*/\" */");
+    assert_eq!(errors, ["1:6 Unterminated string"]);
+}
+
+#[test]
+fn unterminated_nested_block_comment_points_at_the_outermost_opener() {
+    let (errors, tokens) = scan_errors("1 /* a\n/* b */");
+    assert_eq!(errors, ["1:2 Unterminated block comment"]);
+    assert_eq!(tokens, vec![tok!(Number "1" @ 1:0), tok!(Eof "" @ 2:7)]);
+}
+
+// escape sequences
+
+#[test]
+fn string_ending_in_an_escaped_quote() {
+    assert_eq!(
+        scan(r#""say \"hi\"";"#),
+        vec![tok!(String r#""say \"hi\"""# @ 1:0, Literal::Str("say \"hi\"".into())), tok!(Semicolon ";" @ 1:12), tok!(Eof "" @ 1:13)]
+    );
+}
+
+#[test]
+fn string_of_a_lone_escaped_backslash() {
+    assert_eq!(scan(r#""\\""#), vec![tok!(String r#""\\""# @ 1:0, Literal::Str("\\".into())), tok!(Eof "" @ 1:4)]);
+}
+
+#[test]
+fn invalid_escape_is_reported_at_its_backslash() {
+    let (errors, _) = scan_errors("var s = \"ok \\q\";");
+    assert_eq!(errors, ["1:12 Invalid escape sequence '\\q'"]);
+}
+
+// unterminated strings
+
+#[test]
+fn unterminated_string_is_reported_at_its_opening_quote() {
+    let (errors, tokens) = scan_errors("var a = \"hello");
+    assert_eq!(errors, ["1:8 Unterminated string"]);
+    assert_eq!(tokens, vec![tok!(Var "var" @ 1:0), tok!(Identifier "a" @ 1:4), tok!(Equal "=" @ 1:6), tok!(Eof "" @ 1:14)]);
+}
+
+// multi-line strings
+
+#[test]
+fn error_after_a_three_line_string_is_on_the_right_line() {
+    let (errors, tokens) = scan_errors("var s = \"one\ntwo\nthree\";\n\n  @ x");
+    assert_eq!(errors, ["5:2 Unexpected character '@'"]);
+    assert_eq!(tokens[3], tok!(String "\"one\ntwo\nthree\"" @ 1:8));
+    assert_eq!(&tokens[4..], [tok!(Semicolon ";" @ 3:6), tok!(Identifier "x" @ 5:4), tok!(Eof "" @ 5:5)]);
+}
+
+// scan errors are returned
+
+#[test]
+fn scan_tokens_returns_the_errors_of_bad_input() {
+    let mut scanner = Scanner::new("1.2.3");
+    let errors = scanner.scan_tokens().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].span.line, errors[0].span.col), (1, 3));
+    assert_eq!(errors[0].message, "Did not expect '.'");
+}
+
+// string literals
+
+#[test]
+fn string_literal_is_its_contents_without_quotes() {
+    let tokens = scan("x = \"abc\"");
+    assert_eq!(tokens[2], tok!(String "\"abc\"" @ 1:4, Literal::Str("abc".into())));
+    assert_eq!(tokens[2].lexeme(), "\"abc\"");
+}
+
+// a dot after a number
+
+#[test]
+fn trailing_dot_is_not_part_of_the_number() {
+    assert_eq!(scan("1."), vec![tok!(Number "1" @ 1:0), tok!(Dot "." @ 1:1), tok!(Eof "" @ 1:2)]);
+    assert_eq!(
+        scan("123.abs"),
+        vec![tok!(Number "123" @ 1:0), tok!(Dot "." @ 1:3), tok!(Identifier "abs" @ 1:4), tok!(Eof "" @ 1:7)]
+    );
+}
+
+#[test]
+fn digit_after_the_dot_makes_a_fraction() {
+    assert_eq!(scan("1.2"), vec![tok!(Number "1.2" @ 1:0), tok!(Eof "" @ 1:3)]);
+}
+
+#[test]
+fn two_dots_end_the_number_at_the_first() {
+    assert_eq!(scan("1..2"), vec![tok!(Number "1" @ 1:0), tok!(Dot "." @ 1:1), tok!(Number ".2" @ 1:2), tok!(Eof "" @ 1:4)]);
+}
+
+// unicode identifiers
+
+#[test]
+fn accented_identifiers_next_to_operators() {
+    // columns count characters, so `é` and `á` are one column each
+    assert_eq!(
+        scan("variável+é=数;"),
+        vec![
+            tok!(Identifier "variável" @ 1:0),
+            tok!(Plus "+" @ 1:8),
+            tok!(Identifier "é" @ 1:9),
+            tok!(Equal "=" @ 1:10),
+            tok!(Identifier "数" @ 1:11),
+            tok!(Semicolon ";" @ 1:12),
+            tok!(Eof "" @ 1:13),
+        ]
+    );
+}
+
+#[test]
+fn accented_identifiers_inside_strings_stay_text() {
+    assert_eq!(
+        scan("ação(\"ação\")"),
+        vec![
+            tok!(Identifier "ação" @ 1:0),
+            tok!(LeftParen "(" @ 1:4),
+            tok!(String "\"ação\"" @ 1:5),
+            tok!(RightParen ")" @ 1:11),
+            tok!(Eof "" @ 1:12),
+        ]
+    );
+}
+
+#[test]
+fn keywords_match_exactly() {
+    assert_eq!(types("für for"), [TokenType::Identifier, TokenType::For, TokenType::Eof]);
+}
+
+#[test]
+fn non_ascii_digits_are_unexpected_characters() {
+    let (errors, tokens) = scan_errors("1 + ٣;");
+    assert_eq!(errors, ["1:4 Unexpected character '٣'"]);
+    assert_eq!(tokens, vec![tok!(Number "1" @ 1:0), tok!(Plus "+" @ 1:2), tok!(Semicolon ";" @ 1:5), tok!(Eof "" @ 1:6)]);
+    let (errors, tokens) = scan_errors("a.٣");
+    assert_eq!(errors, ["1:2 Unexpected character '٣'"]);
+    assert_eq!(tokens, vec![tok!(Identifier "a" @ 1:0), tok!(Dot "." @ 1:1), tok!(Eof "" @ 1:3)]);
+}
+
+// CRLF line endings
+
+#[test]
+fn crlf_file_scans_like_the_lf_one() {
+    let lf = "var a = 1;\nprint a; // one\n{\n  a = \"x\";\n}\n";
+    let crlf = lf.replace('\n', "\r\n");
+    // tokens compare by type, literal and span
+    assert_eq!(scan(&crlf), scan(lf));
+}
+
+#[test]
+fn carriage_return_ends_an_identifier_and_a_comment() {
+    assert_eq!(
+        scan("abc\r\n// c\r\nd"),
+        vec![tok!(Identifier "abc" @ 1:0), tok!(Comment "// c" @ 2:0, Literal::Str(" c".into())), tok!(Identifier "d" @ 3:0), tok!(Eof "" @ 3:1)]
+    );
+}
+
+// modulo
+
+#[test]
+fn percent_between_identifiers_and_numbers() {
+    assert_eq!(scan("a % b"), vec![tok!(Identifier "a" @ 1:0), tok!(Percent "%" @ 1:2), tok!(Identifier "b" @ 1:4), tok!(Eof "" @ 1:5)]);
+    assert_eq!(scan("7%2"), vec![tok!(Number "7" @ 1:0), tok!(Percent "%" @ 1:1), tok!(Number "2" @ 1:2), tok!(Eof "" @ 1:3)]);
+}
+
+// question mark and colon
+
+#[test]
+fn question_and_colon_end_identifiers_and_numbers() {
+    assert_eq!(
+        scan("a?b:c"),
+        vec![
+            tok!(Identifier "a" @ 1:0),
+            tok!(Question "?" @ 1:1),
+            tok!(Identifier "b" @ 1:2),
+            tok!(Colon ":" @ 1:3),
+            tok!(Identifier "c" @ 1:4),
+            tok!(Eof "" @ 1:5),
+        ]
+    );
+    assert_eq!(types("1?2:3"), [TokenType::Number, TokenType::Question, TokenType::Number, TokenType::Colon, TokenType::Number, TokenType::Eof]);
+}
+
+#[test]
+fn colon_stands_on_its_own() {
+    assert_eq!(types(": a:"), [TokenType::Colon, TokenType::Identifier, TokenType::Colon, TokenType::Eof]);
+}
+
+// break and continue
+
+#[test]
+fn break_in_a_loop_is_a_keyword() {
+    use TokenType::*;
+    assert_eq!(types("while (true) { break; }"), [While, LeftParen, True, RightParen, LeftBrace, Break, Semicolon, RightBrace, Eof]);
+    assert_eq!(types("continue breaks"), [Continue, Identifier, Eof]);
+}
+
+// increment and decrement
+
+#[test]
+fn increment_and_decrement_statements() {
+    assert_eq!(scan("i++;"), vec![tok!(Identifier "i" @ 1:0), tok!(PlusPlus "++" @ 1:1), tok!(Semicolon ";" @ 1:3), tok!(Eof "" @ 1:4)]);
+    assert_eq!(scan("--i;"), vec![tok!(MinusMinus "--" @ 1:0), tok!(Identifier "i" @ 1:2), tok!(Semicolon ";" @ 1:3), tok!(Eof "" @ 1:4)]);
+}
+
+#[test]
+fn three_minuses_are_a_decrement_then_a_minus() {
+    assert_eq!(
+        scan("a---b"),
+        vec![tok!(Identifier "a" @ 1:0), tok!(MinusMinus "--" @ 1:1), tok!(Minus "-" @ 1:3), tok!(Identifier "b" @ 1:4), tok!(Eof "" @ 1:5)]
+    );
+}
+
+#[test]
+fn minuses_split_by_whitespace_stay_apart() {
+    use TokenType::*;
+    assert_eq!(types("a - -b"), [Identifier, Minus, Minus, Identifier, Eof]);
+    assert_eq!(types("a- -b"), [Identifier, Minus, Minus, Identifier, Eof]);
+}
+
+// bitwise operators
+
+#[test]
+fn shifts_and_comparisons_split_right() {
+    assert_eq!(
+        scan("a << 2 >= b"),
+        vec![
+            tok!(Identifier "a" @ 1:0),
+            tok!(LessLess "<<" @ 1:2),
+            tok!(Number "2" @ 1:5),
+            tok!(GreaterEqual ">=" @ 1:7),
+            tok!(Identifier "b" @ 1:10),
+            tok!(Eof "" @ 1:11),
+        ]
+    );
+    use TokenType::*;
+    assert_eq!(types("a>>=b"), [Identifier, GreaterGreater, Equal, Identifier, Eof]);
+    assert_eq!(types("a<<<b"), [Identifier, LessLess, Less, Identifier, Eof]);
+    assert_eq!(types("a&b|c^d"), [Identifier, Amp, Identifier, Pipe, Identifier, Caret, Identifier, Eof]);
+}
+
+// arrow
+
+#[test]
+fn adjacent_minus_and_greater() {
+    use TokenType::*;
+    let cases: &[(&str, &[TokenType])] = &[
+        ("a->b", &[Identifier, Arrow, Identifier, Eof]),
+        ("a - >b", &[Identifier, Minus, Greater, Identifier, Eof]),
+        ("a- >b", &[Identifier, Minus, Greater, Identifier, Eof]),
+        ("a-->b", &[Identifier, MinusMinus, Greater, Identifier, Eof]),
+        ("a->=b", &[Identifier, Arrow, Equal, Identifier, Eof]),
+        ("a>-b", &[Identifier, Greater, Minus, Identifier, Eof]),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(types(source), *expected, "{:?}", source);
+    }
+}
+
+#[test]
+fn arrow_column() {
+    assert_eq!(scan("x -> x"), vec![tok!(Identifier "x" @ 1:0), tok!(Arrow "->" @ 1:2), tok!(Identifier "x" @ 1:5), tok!(Eof "" @ 1:6)]);
+}
+
+// raw strings
+
+#[test]
+fn raw_string_keeps_the_escape_an_ordinary_string_processes() {
+    assert_eq!(scan(r#""a\nb""#)[0].literal(), &Literal::Str("a\nb".into()));
+    assert_eq!(scan(r#"r"a\nb""#)[0].literal(), &Literal::Str(r"a\nb".into()));
+}
+
+#[test]
+fn r_alone_is_an_identifier() {
+    assert_eq!(
+        scan("r = ra;"),
+        vec![tok!(Identifier "r" @ 1:0), tok!(Equal "=" @ 1:2), tok!(Identifier "ra" @ 1:4), tok!(Semicolon ";" @ 1:6), tok!(Eof "" @ 1:7)]
+    );
+}
+
+#[test]
+fn unterminated_raw_string() {
+    let (errors, tokens) = scan_errors("x r\" unterminated");
+    assert_eq!(errors, ["1:2 Unterminated string"]);
+    assert_eq!(tokens, vec![tok!(Identifier "x" @ 1:0), tok!(Eof "" @ 1:17)]);
+}
+
+// every lexical error is reported
+
+#[test]
+fn three_bad_characters_are_three_errors() {
+    let (errors, tokens) = scan_errors("var a = 1 @ 2;\nprint a $;\n` a");
+    assert_eq!(errors, ["1:10 Unexpected character '@'", "2:8 Unexpected character '$'", "3:0 Unexpected character '`'"]);
+    use TokenType::*;
+    assert_eq!(
+        tokens.iter().map(Token::ttype).collect::<Vec<_>>(),
+        [Var, Identifier, Equal, Number, Number, Semicolon, Print, Identifier, Semicolon, Identifier, Eof]
+    );
+    assert_eq!(tokens[4], tok!(Number "2" @ 1:12));
+    assert_eq!(tokens[9], tok!(Identifier "a" @ 3:2));
+}
+
+// input ending mid-token
+
+#[test]
+fn pending_token_is_flushed_at_the_end_of_input() {
+    assert_eq!(scan("a <"), vec![tok!(Identifier "a" @ 1:0), tok!(Less "<" @ 1:2), tok!(Eof "" @ 1:3)]);
+    assert_eq!(scan("a +"), vec![tok!(Identifier "a" @ 1:0), tok!(Plus "+" @ 1:2), tok!(Eof "" @ 1:3)]);
+    assert_eq!(scan("a + 12"), vec![tok!(Identifier "a" @ 1:0), tok!(Plus "+" @ 1:2), tok!(Number "12" @ 1:4), tok!(Eof "" @ 1:6)]);
+    assert_eq!(scan("1.25"), vec![tok!(Number "1.25" @ 1:0), tok!(Eof "" @ 1:4)]);
+    assert_eq!(scan("1 + abc"), vec![tok!(Number "1" @ 1:0), tok!(Plus "+" @ 1:2), tok!(Identifier "abc" @ 1:4), tok!(Eof "" @ 1:7)]);
+    assert_eq!(scan("a ."), vec![tok!(Identifier "a" @ 1:0), tok!(Dot "." @ 1:2), tok!(Eof "" @ 1:3)]);
+}
+
+#[test]
+fn keyword_at_the_end_of_input() {
+    assert_eq!(scan("return"), vec![tok!(Return "return" @ 1:0), tok!(Eof "" @ 1:6)]);
+}
+
+// no trailing newline needed
+
+#[test]
+fn scanner_alone_scans_a_whole_declaration() {
+    let source = "var x = 10";
+    let mut scanner = Scanner::new(source);
+    let tokens: Vec<_> = scanner.scan_tokens().unwrap().iter().map(|token| without_symbol(token.clone(), source)).collect();
+    assert_eq!(
+        tokens,
+        vec![tok!(Var "var" @ 1:0), tok!(Identifier "x" @ 1:4), tok!(Equal "=" @ 1:6), tok!(Number "10" @ 1:8), tok!(Eof "" @ 1:10)]
+    );
+    assert_eq!(tokens[3].bytes(), 8..10);
+}
+
+// identifier characters
+
+#[test]
+fn underscores_and_digits_stay_in_an_identifier() {
+    assert_eq!(scan("a_b1"), vec![tok!(Identifier "a_b1" @ 1:0), tok!(Eof "" @ 1:4)]);
+}
+
+#[test]
+fn minus_splits_identifiers() {
+    assert_eq!(scan("a-b"), vec![tok!(Identifier "a" @ 1:0), tok!(Minus "-" @ 1:1), tok!(Identifier "b" @ 1:2), tok!(Eof "" @ 1:3)]);
+}
+
+#[test]
+fn bad_character_inside_an_identifier_is_reported_at_itself() {
+    let (errors, tokens) = scan_errors("a#b");
+    assert_eq!(errors, ["1:1 Unexpected character '#'"]);
+    assert_eq!(tokens, vec![tok!(Identifier "a" @ 1:0), tok!(Identifier "b" @ 1:2), tok!(Eof "" @ 1:3)]);
+}
+
+// slash
+
+#[test]
+fn slash_before_an_operand_is_division() {
+    assert_eq!(scan("1/2"), vec![tok!(Number "1" @ 1:0), tok!(Slash "/" @ 1:1), tok!(Number "2" @ 1:2), tok!(Eof "" @ 1:3)]);
+    assert_eq!(scan("a / b"), vec![tok!(Identifier "a" @ 1:0), tok!(Slash "/" @ 1:2), tok!(Identifier "b" @ 1:4), tok!(Eof "" @ 1:5)]);
+    assert_eq!(
+        scan("a/(b)"),
+        vec![tok!(Identifier "a" @ 1:0), tok!(Slash "/" @ 1:1), tok!(LeftParen "(" @ 1:2), tok!(Identifier "b" @ 1:3), tok!(RightParen ")" @ 1:4), tok!(Eof "" @ 1:5)]
+    );
+}
+
+#[test]
+fn slash_equal_is_two_tokens() {
+    assert_eq!(
+        scan("a/=b"),
+        vec![tok!(Identifier "a" @ 1:0), tok!(Slash "/" @ 1:1), tok!(Equal "=" @ 1:2), tok!(Identifier "b" @ 1:3), tok!(Eof "" @ 1:4)]
+    );
+}
+
+// comment or slash at the end of input
+
+#[test]
+fn comment_ends_with_the_input() {
+    let mut scanner = Scanner::new("1+1 // done");
+    assert_eq!(
+        scanner.scan_tokens().unwrap(),
+        [tok!(Number "1" @ 1:0), tok!(Plus "+" @ 1:1), tok!(Number "1" @ 1:2), tok!(Eof "" @ 1:11)]
+    );
+}
+
+#[test]
+fn slash_at_the_end_of_input() {
+    let mut scanner = Scanner::new("1/");
+    assert_eq!(scanner.scan_tokens().unwrap(), [tok!(Number "1" @ 1:0), tok!(Slash "/" @ 1:1), tok!(Eof "" @ 1:2)]);
+}
+
+// comment tokens
+
+#[test]
+fn comment_tokens_come_in_source_order() {
+    let source = "// header\nvar a = 1; // one\n\nprint a;\n  // last";
+    let tokens = scan(source);
+    let comments: Vec<_> = tokens.iter().filter(|token| token.ttype() == TokenType::Comment).cloned().collect();
+    assert_eq!(
+        comments,
+        vec![tok!(Comment "// header" @ 1:0), tok!(Comment "// one" @ 2:11), tok!(Comment "// last" @ 5:2)]
+    );
+    // and sit between the code around them
+    assert_eq!(tokens[0].ttype(), TokenType::Comment);
+    assert_eq!(tokens[6].ttype(), TokenType::Comment);
+    assert_eq!(tokens[7], tok!(Print "print" @ 4:0));
+}
+
+// scan -> render -> scan
+
+// pieces of source that scan to one or a few tokens
+const FRAGMENTS: &[&str] = &[
+    "(", ")", "{", "}", "[", "]", ",", ".", ";", ":", "?", "-", "+", "*", "/", "%", "!", "=", "<", ">", "&", "|", "^",
+    "!=", "==", "<=", ">=", "<<", ">>", "++", "--", "->", "and", "or", "var", "fun", "class", "print", "return",
+    "while", "true", "false", "nil", "this", "a", "b1", "_c", "ação", "0", "12", "3.5", ".25", "1e3", "1_000",
+    "\"\"", "\"s t\"", "\"a\\nb\"", "\"q\\\"\"", "r\"x\\y\"", "\"a ${b} c\"", "\"one\ntwo\"", "// note\n",
+];
+
+// xorshift, so a failing sequence can be found again from its seed
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+fn meaning(tokens: &[Token]) -> Vec<(TokenType, String)> {
+    tokens.iter().map(|token| (token.ttype(), format!("{:?}", token.literal()))).collect()
+}
+
+#[test]
+fn rendered_tokens_scan_back_to_the_same_tokens() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut checked = 0;
+    for case in 0..2000 {
+        let mut source = String::new();
+        for _ in 0..1 + rng.below(12) {
+            source.push_str(FRAGMENTS[rng.below(FRAGMENTS.len())]);
+            // fragments run together too, so `-` `-` can become `--`, as long as the source still scans
+            source.push_str(["", " ", "\n"][rng.below(3)]);
+        }
+        let mut scanner = Scanner::new(&source);
+        scanner.comments = true;
+        let Ok(tokens) = scanner.scan_tokens() else { continue };
+        let rendered = render(tokens);
+        let mut rescanner = Scanner::new(&rendered);
+        rescanner.comments = true;
+        match rescanner.scan_tokens() {
+            Ok(rescanned) => assert_eq!(meaning(rescanned), meaning(tokens), "case {}: {:?} rendered as {:?}", case, source, rendered),
+            Err(errors) => panic!("case {}: {:?} rendered as {:?} scanned with errors {:?}", case, source, rendered, errors),
+        }
+        checked += 1;
+    }
+    assert!(checked > 1000, "only {} of the sequences scanned", checked);
+}
+
+// spans
+
+#[test]
+fn token_at_column_zero() {
+    assert_eq!(scan("a\nb"), vec![tok!(Identifier "a" @ 1:0), tok!(Identifier "b" @ 2:0), tok!(Eof "" @ 2:1)]);
+}
+
+#[test]
+fn token_at_the_end_of_a_line() {
+    assert_eq!(
+        scan("x = 1;\ny"),
+        vec![
+            tok!(Identifier "x" @ 1:0),
+            tok!(Equal "=" @ 1:2),
+            tok!(Number "1" @ 1:4),
+            tok!(Semicolon ";" @ 1:5),
+            tok!(Identifier "y" @ 2:0),
+            tok!(Eof "" @ 2:1),
+        ]
+    );
+}
+
+#[test]
+fn token_after_a_two_character_operator() {
+    let tokens = scan("a>=b != c");
+    assert_eq!(tokens[1], tok!(GreaterEqual ">=" @ 1:1));
+    assert_eq!(tokens[1].span().len, 2);
+    assert_eq!(tokens[2], tok!(Identifier "b" @ 1:3));
+    assert_eq!(tokens[4], tok!(Identifier "c" @ 1:8));
+}
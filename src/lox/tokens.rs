@@ -1,56 +1,275 @@
+use std::{borrow::Cow, fmt, ops::Range};
+
+use crate::lox::interner::Symbol;
+
+// Where a token or error sits: 1-based `line`, 0-based `col` of its first character, and `len` in
+// characters. Columns count characters, not bytes, and a token spanning lines (a multi-line string)
+// has a len running past the end of its first line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+    pub len: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, col: usize, len: usize) -> Self {
+        Self { line, col, len }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct Token {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token<'src> {
     ttype: TokenType,
-    literal: Option<String>,
-    line: usize,
-    offset: usize,
+    literal: Literal<'src>,
+    span: Span,
+    // source text of the token, quotes and escapes included; borrowed from the source unless the token was
+    // read back from JSON, where escaped quotes mean it can't always be borrowed from the input
+    lexeme: Cow<'src, str>,
+    // byte range of the lexeme in the source
+    bytes: Range<usize>,
 }
 
-impl Token {
-    pub fn new(ttype: TokenType, literal: Option<String>, line: usize, offset: usize) -> Self {
+impl<'src> Token<'src> {
+    // `col` is where the token starts, its len comes from the lexeme
+    pub fn new(ttype: TokenType, literal: Literal<'src>, line: usize, col: usize, source: &'src str, bytes: Range<usize>) -> Self {
+        let lexeme = &source[bytes.clone()];
         Self {
             ttype,
             literal,
-            line,
-            offset,
+            span: Span::new(line, col, lexeme.chars().count()),
+            lexeme: Cow::Borrowed(lexeme),
+            bytes,
+        }
+    }
+    pub fn ttype(&self) -> TokenType {
+        self.ttype
+    }
+    pub fn literal(&self) -> &Literal<'src> {
+        &self.literal
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn lexeme(&self) -> &str {
+        &self.lexeme
+    }
+    pub fn bytes(&self) -> Range<usize> {
+        self.bytes.clone()
+    }
+    // the two one-character `ttype` tokens a two-character token like `--` is made of
+    pub fn halves(&self, ttype: TokenType) -> (Self, Self) {
+        let half = |start: usize, col: usize| {
+            let within = start - self.bytes.start..start - self.bytes.start + 1;
+            Token {
+                ttype,
+                literal: Literal::None,
+                span: Span::new(self.span.line, col, 1),
+                lexeme: match &self.lexeme {
+                    Cow::Borrowed(lexeme) => Cow::Borrowed(&lexeme[within]),
+                    Cow::Owned(lexeme) => Cow::Owned(lexeme[within].to_string()),
+                },
+                bytes: start..start + 1,
+            }
+        };
+        (half(self.bytes.start, self.span.col), half(self.bytes.start + 1, self.span.col + 1))
+    }
+    // a copy that no longer borrows the source, for a declaration the interpreter keeps past the run that
+    // parsed it
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            ttype: self.ttype,
+            literal: self.literal.into_owned(),
+            span: self.span,
+            lexeme: Cow::Owned(self.lexeme.into_owned()),
+            bytes: self.bytes,
+        }
+    }
+    // for tokens scanned from a chunk of a larger input, `by` is where the chunk starts
+    pub fn shift_bytes(&mut self, by: usize) {
+        self.bytes = self.bytes.start + by..self.bytes.end + by;
+    }
+}
+
+// Tokens are equal when they mean the same to the parser at the same span; the lexeme and byte range
+// are left out.
+impl PartialEq for Token<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ttype == other.ttype && self.literal == other.literal && self.span == other.span
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.ttype, self.lexeme)?;
+        if self.literal != Literal::None {
+            write!(f, " {}", self.literal)?;
         }
+        write!(f, " [line: {}; col: {}; len: {}; bytes: {:?}]", self.span.line, self.span.col, self.span.len, self.bytes)
+    }
+}
+
+// Source text that scans back to the same token types and literals (not the same bytes): tokens stay
+// on their original lines, one space apart, except between the pieces of an interpolated string where
+// a space would become part of the string.
+pub fn render(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut line = 1;
+    let mut previous: Option<&Token> = None;
+    for token in tokens {
+        if token.ttype == TokenType::Eof {
+            break;
+        }
+        let glued = previous.is_some_and(|previous| {
+            matches!(
+                (previous.ttype, token.ttype),
+                (TokenType::String, TokenType::InterpStart) | (TokenType::InterpEnd, TokenType::String)
+            )
+        });
+        if !glued {
+            if token.span.line > line {
+                out.push_str(&"\n".repeat(token.span.line - line));
+                line = token.span.line;
+            } else if previous.is_some_and(|previous| previous.ttype == TokenType::Comment) {
+                // a comment runs to the end of its line
+                out.push('\n');
+                line += 1;
+            } else if previous.is_some() {
+                out.push(' ');
+            }
+        }
+        out.push_str(&token.lexeme);
+        line += token.lexeme.matches('\n').count();
+        previous = Some(token);
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Literal<'src> {
+    Number(f64),
+    // borrowed from the source unless escapes changed the text
+    Str(Cow<'src, str>),
+    // an identifier's name, see Scanner::interner
+    Symbol(Symbol),
+    Bool(bool),
+    Nil,
+    None,
+}
+
+impl Literal<'_> {
+    pub fn into_owned(self) -> Literal<'static> {
+        match self {
+            Literal::Number(number) => Literal::Number(number),
+            Literal::Str(string) => Literal::Str(Cow::Owned(string.into_owned())),
+            Literal::Symbol(symbol) => Literal::Symbol(symbol),
+            Literal::Bool(value) => Literal::Bool(value),
+            Literal::Nil => Literal::Nil,
+            Literal::None => Literal::None,
+        }
+    }
+}
+
+impl fmt::Display for Literal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Number(number) => write!(f, "{}", number),
+            Literal::Str(string) => write!(f, "{}", string),
+            Literal::Symbol(symbol) => write!(f, "{}", symbol),
+            Literal::Bool(value) => write!(f, "{}", value),
+            Literal::Nil => write!(f, "nil"),
+            Literal::None => Ok(()),
+        }
+    }
+}
+
+impl TokenType {
+    pub fn is_keyword(self) -> bool {
+        matches!(
+            self,
+            TokenType::And
+                | TokenType::Break
+                | TokenType::Case
+                | TokenType::Class
+                | TokenType::Continue
+                | TokenType::Default
+                | TokenType::Else
+                | TokenType::False
+                | TokenType::Fun
+                | TokenType::For
+                | TokenType::If
+                | TokenType::Nil
+                | TokenType::Or
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Super
+                | TokenType::Switch
+                | TokenType::This
+                | TokenType::True
+                | TokenType::Var
+                | TokenType::While
+        )
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenType {
     // Single-character tokens.
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
-    Minus,
-    Plus,
     Semicolon,
     Star,
+    Percent,
+    Question,
+    Colon,
+    Amp,
+    Pipe,
+    Caret,
 
     // One or two character tokens.
     Bang,
     BangEqual,
+    Minus,
+    Plus,
     Equal,
     EqualEqual,
     Greater,
     GreaterEqual,
+    GreaterGreater,
     Less,
     LessEqual,
+    LessLess,
+    PlusPlus,
+    MinusMinus,
+    Arrow,
     Slash,
-    // SlashSlash (no need though since we ignore comments [for now?])
+    // "//" line comment, literal is the text after the slashes; only kept when the scanner is asked to
+    Comment,
 
     // Literals.
     Identifier,
     String,
+    InterpStart,
+    InterpEnd,
     Number,
 
     // Keywords.
     And,
+    Break,
+    Case,
     Class,
+    Continue,
+    Default,
     Else,
     False,
     Fun,
@@ -61,6 +280,7 @@ pub enum TokenType {
     Print,
     Return,
     Super,
+    Switch,
     This,
     True,
     Var,
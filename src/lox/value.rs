@@ -0,0 +1,164 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::lox::callable::{LoxFunction, NativeFunction};
+use crate::lox::class::{LoxClass, LoxInstance};
+
+// what a Lox expression evaluates to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    Nil,
+    Function(Rc<LoxFunction>),
+    Native(Rc<NativeFunction>),
+    Class(Rc<LoxClass>),
+    // shared and mutable: every copy of the value sees a field set through any other
+    Instance(Rc<RefCell<LoxInstance>>),
+}
+
+impl Value {
+    // Lox's truthiness, for every place a value is taken as a condition: only nil and false are falsey, 0
+    // and "" included
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+    // `==`: values of different types are never equal, there's no coercion (`1 == "1"` is false). Numbers
+    // follow IEEE 754 like the arithmetic does, so `0 == -0` is true and NaN isn't equal to itself.
+    pub fn lox_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(left), Value::Number(right)) => left == right,
+            (Value::Str(left), Value::Str(right)) => left == right,
+            (Value::Bool(left), Value::Bool(right)) => left == right,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Function(left), Value::Function(right)) => Rc::ptr_eq(left, right),
+            (Value::Native(left), Value::Native(right)) => Rc::ptr_eq(left, right),
+            (Value::Class(left), Value::Class(right)) => Rc::ptr_eq(left, right),
+            (Value::Instance(left), Value::Instance(right)) => Rc::ptr_eq(left, right),
+            _ => false,
+        }
+    }
+    // the form the REPL echoes: like Display but with strings quoted, so `"1"` and `1` look different
+    pub fn repr(&self) -> String {
+        match self {
+            Value::Str(string) => format!("{:?}", string),
+            value => value.to_string(),
+        }
+    }
+}
+
+// What `print` shows. Numbers are f64 but integral ones print without a fraction (`7`, `-0`); from 1e21 up,
+// and below 1e-7 except for zero, they switch to an exponent (`1e21`, `1e-300`) instead of spelling out
+// every digit.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(number) if number.is_finite() && number.abs() >= 1e21 => write!(f, "{:e}", number),
+            Value::Number(number) if *number != 0.0 && number.abs() < 1e-7 => write!(f, "{:e}", number),
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Str(string) => write!(f, "{}", string),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Nil => write!(f, "nil"),
+            Value::Function(function) => write!(f, "<fn {}>", function.name()),
+            Value::Native(_) => write!(f, "<native fn>"),
+            Value::Class(class) => write!(f, "{}", class.name),
+            Value::Instance(instance) => write!(f, "{} instance", instance.borrow().class.name),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Value::Number(number)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(string: String) -> Self {
+        Value::Str(string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    #[test]
+    fn numbers_print_without_a_trailing_fraction() {
+        assert_eq!(Value::from(7.0).to_string(), "7");
+        assert_eq!(Value::from(0.5).to_string(), "0.5");
+        assert_eq!(Value::from(-0.0).to_string(), "-0");
+        assert_eq!(Value::from(0.0000001).to_string(), "0.0000001");
+    }
+
+    #[test]
+    fn huge_and_tiny_numbers_print_with_an_exponent() {
+        assert_eq!(Value::from(1e21).to_string(), "1e21");
+        assert_eq!(Value::from(-1e21).to_string(), "-1e21");
+        assert_eq!(Value::from(1e20).to_string(), "100000000000000000000");
+        assert_eq!(Value::from(1e-300).to_string(), "1e-300");
+        assert_eq!(Value::from(-2.5e-8).to_string(), "-2.5e-8");
+        assert_eq!(Value::from(f64::INFINITY).to_string(), "inf");
+    }
+
+    #[test]
+    fn other_values() {
+        assert_eq!(Value::Nil.to_string(), "nil");
+        assert_eq!(Value::from(true).to_string(), "true");
+        assert_eq!(Value::from("a \"b\"".to_string()).to_string(), "a \"b\"");
+        assert_eq!(Value::from("a \"b\"".to_string()).repr(), "\"a \\\"b\\\"\"");
+        assert_eq!(Value::Nil.repr(), "nil");
+    }
+
+    #[test]
+    fn only_nil_and_false_are_falsey() {
+        assert!(!Value::Nil.is_truthy());
+        assert!(!Value::from(false).is_truthy());
+        assert!(Value::from(true).is_truthy());
+        assert!(Value::from(0.0).is_truthy());
+        assert!(Value::from(-0.0).is_truthy());
+        assert!(Value::from(f64::NAN).is_truthy());
+        assert!(Value::from(String::new()).is_truthy());
+        assert!(Value::from("false".to_string()).is_truthy());
+    }
+
+    #[test]
+    fn equality_within_a_type() {
+        assert!(Value::Nil.lox_eq(&Value::Nil));
+        assert!(Value::from(1.0).lox_eq(&Value::from(1.0)));
+        assert!(!Value::from(1.0).lox_eq(&Value::from(2.0)));
+        assert!(Value::from(0.0).lox_eq(&Value::from(-0.0)));
+        assert!(!Value::from(f64::NAN).lox_eq(&Value::from(f64::NAN)));
+        assert!(Value::from("a".to_string()).lox_eq(&Value::from("a".to_string())));
+        assert!(!Value::from("a".to_string()).lox_eq(&Value::from("A".to_string())));
+        assert!(Value::from(false).lox_eq(&Value::from(false)));
+        assert!(!Value::from(true).lox_eq(&Value::from(false)));
+    }
+
+    #[test]
+    fn values_of_different_types_are_never_equal() {
+        let values = [
+            Value::Nil,
+            Value::from(0.0),
+            Value::from(1.0),
+            Value::from(String::new()),
+            Value::from("1".to_string()),
+            Value::from(false),
+        ];
+        for (i, left) in values.iter().enumerate() {
+            for (j, right) in values.iter().enumerate() {
+                if std::mem::discriminant(left) != std::mem::discriminant(right) {
+                    assert!(!left.lox_eq(right), "{:?} == {:?}", left, right);
+                } else if i == j {
+                    assert!(left.lox_eq(right), "{:?} != {:?}", left, right);
+                }
+            }
+        }
+    }
+}
@@ -1,20 +1,53 @@
-mod lox;
+use interpreter::lox::{ast::printer::AstFormat, Lox};
+use std::{env, path::Path, process, thread};
+
+const USAGE: &str = "Usage: jlox [--parse|--check] [--tokens] [--render] [--print-ast[=lisp|rpn]] [--spans] [--ieee-division] [--emit=ast-json] [script]";
+
+// Lox calls recurse on the Rust stack, a few frames each. The CLI runs on a thread with this much, and lets
+// calls take all but an eighth of it (kept for the frames below the outermost call), so deep recursion runs
+// and unbounded recursion still ends in a "Stack overflow" runtime error rather than an abort.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
-use lox::Lox;
-use std::{env, path::Path, process};
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = thread::Builder::new().stack_size(STACK_SIZE).spawn(cli).unwrap();
+    cli.join().unwrap();
+}
 
-    match args.len() {
-        1 => {
+fn cli() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (flags, scripts): (Vec<&String>, Vec<&String>) = args.iter().partition(|arg| arg.starts_with("--"));
+
+    let mut lox = Lox::new();
+    lox.max_call_stack = STACK_SIZE - STACK_SIZE / 8;
+    for flag in flags {
+        match flag.as_str() {
+            "--parse" | "--check" => lox.check_only = true,
+            "--tokens" => lox.debug_tokens = true,
+            "--render" => lox.render_tokens = true,
+            "--print-ast" | "--print-ast=lisp" => lox.print_ast = Some(AstFormat::Lisp),
+            "--print-ast=rpn" => lox.print_ast = Some(AstFormat::Rpn),
+            "--spans" => lox.print_spans = true,
+            "--ieee-division" => lox.ieee_division = true,
+            #[cfg(feature = "serde")]
+            "--emit=ast-json" => lox.emit_ast_json = true,
+            _ => {
+                println!("{}", USAGE);
+                process::exit(64);
+            }
+        }
+    }
+
+    match scripts.len() {
+        0 => {
             println!("wtf");
-            Lox::run_prompt().unwrap();
+            lox.run_prompt().unwrap();
         }
-        2 => {
-            Lox::run_file(Path::new(&args[0])).unwrap();
+        1 => {
+            let code = lox.run_file(Path::new(scripts[0])).unwrap();
+            process::exit(code);
         }
         _ => {
-            println!("Usage: jlox [script]");
+            println!("{}", USAGE);
             process::exit(64);
         }
     }
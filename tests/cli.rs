@@ -0,0 +1,128 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+// runs the interpreter binary on `source`, saved to a script named after the test so tests can run in parallel
+fn run(name: &str, source: &str, flags: &[&str]) -> Output {
+    let script = env::temp_dir().join(format!("lox-cli-{}-{}.lox", name, std::process::id()));
+    fs::write(&script, source).unwrap();
+    let output = run_script(&script, flags);
+    fs::remove_file(&script).unwrap();
+    output
+}
+
+fn run_script(script: &Path, flags: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_interpreter")).args(flags).arg(script).output().unwrap()
+}
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn unbounded_recursion_is_a_runtime_error() {
+    let output = run("unbounded_recursion", "fun r(n) { return r(n + 1); }\nr(0);\n", &[]);
+    assert_eq!(output.status.code(), Some(70));
+    assert!(stderr(&output).contains("Stack overflow"), "{}", stderr(&output));
+}
+
+#[test]
+fn deep_recursion_runs() {
+    let source = "fun depth(n) { if (n == 0) return 0; return 1 + depth(n - 1); }\nprint depth(5000);\n";
+    let output = run("deep_recursion", source, &[]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "5000\n");
+}
+
+#[test]
+fn every_parse_error_is_reported_before_exiting() {
+    let output = run_script(&fixture("errors.lox"), &[]);
+    assert_eq!(output.status.code(), Some(65));
+    // nothing runs, not even the statements that parsed
+    assert_eq!(stdout(&output), "");
+    let stderr = stderr(&output);
+    let missing_semicolon = stderr.find("[line: 2; col: 13]").expect(&stderr);
+    assert!(stderr[missing_semicolon..].contains("Error msg: Expected ';' after value"), "{}", stderr);
+    let unclosed_paren = stderr.find("[line: 7; col: 12]").expect(&stderr);
+    assert!(stderr[unclosed_paren..].contains("Error msg: Expected ')' after expression"), "{}", stderr);
+    assert!(missing_semicolon < unclosed_paren);
+    assert_eq!(stderr.matches("ERROR").count(), 2, "{}", stderr);
+}
+
+#[test]
+fn scan_errors_exit_with_65() {
+    let output = run("scan_error", "print 1.2.3;\n", &[]);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(stderr(&output).contains("Did not expect '.'"), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ast_json_reads_back_as_the_parsed_tree() {
+    use interpreter::lox::{ast::Stmt, parser::Parser, scanner::Scanner};
+
+    let source = "var a = \"x\\n\" + 1;\nfun f(b) { return b ? -a : nil; }\nclass C < D { m() { this.c[0] = super.m(); } }\n";
+    let output = run("ast_json", source, &["--emit=ast-json"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let json = stdout(&output);
+    let read: Vec<Stmt> = serde_json::from_str(&json).unwrap();
+
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    let (statements, errors) = Parser::new(source, tokens).parse();
+    assert!(errors.is_empty());
+    assert_eq!(read, statements);
+    assert!(json.contains("\"span\":{\"line\":2,\"col\":0,\"len\":"), "{}", json);
+}
+
+#[test]
+fn parse_flag_checks_without_running() {
+    for flag in ["--parse", "--check"] {
+        let output = run_script(&fixture("valid.lox"), &[flag]);
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+        assert_eq!(stdout(&output), "");
+        assert_eq!(stderr(&output), "");
+
+        let output = run_script(&fixture("errors.lox"), &[flag]);
+        assert_eq!(output.status.code(), Some(65));
+        assert_eq!(stdout(&output), "");
+        assert_eq!(stderr(&output).matches("ERROR").count(), 2, "{}", stderr(&output));
+    }
+}
+
+#[test]
+fn valid_fixture_runs() {
+    let output = run_script(&fixture("valid.lox"), &[]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "hello, lox\n42\n");
+}
+
+#[test]
+fn runtime_error_is_reported_at_its_operator_and_exits_with_70() {
+    let output = run_script(&fixture("runtime_error.lox"), &[]);
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(stdout(&output), "before\n");
+    let stderr = stderr(&output);
+    assert!(stderr.contains("[line: 3; col: 8]"), "{}", stderr);
+    assert!(stderr.contains("Error msg: Operands must be two numbers or two strings"), "{}", stderr);
+}
+
+#[test]
+fn ieee_division_flag() {
+    let output = run("ieee_division", "print 1 / 0;\n", &["--ieee-division"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "inf\n");
+    let output = run("division_by_zero", "print 1 / 0;\n", &[]);
+    assert_eq!(output.status.code(), Some(70));
+    assert!(stderr(&output).contains("Division by zero"), "{}", stderr(&output));
+}
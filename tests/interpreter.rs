@@ -0,0 +1,573 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use interpreter::lox::{
+    ast::Stmt, error::RuntimeError, interpreter::Interpreter, parser::Parser, scanner::Scanner, value::Value, Lox,
+};
+
+// what a script printed, shared with the interpreter writing it
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Output {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+fn message(error: &RuntimeError) -> String {
+    format!("{}:{} {}", error.span.line, error.span.col, error.message)
+}
+
+// Runs `source`, which must parse, reading `input` for readLine(): what it printed, and the runtime error
+// that stopped it as `line:col message`.
+fn run_with_input(source: &str, input: &'static str) -> (String, Option<String>) {
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    let (statements, errors) = Parser::new(source, tokens).parse();
+    assert!(errors.is_empty(), "{:?} parsed with errors {:?}", source, errors);
+    interpret(&statements, input)
+}
+
+fn interpret(statements: &[Stmt], input: &'static str) -> (String, Option<String>) {
+    let output = Output::default();
+    let mut interpreter = Interpreter::with_io(Box::new(input.as_bytes()), Box::new(output.clone()));
+    let error = interpreter.interpret(statements).err();
+    (output.text(), error.as_ref().map(message))
+}
+
+// what `source` printed, which must run without errors
+fn run(source: &str) -> String {
+    match run_with_input(source, "") {
+        (output, None) => output,
+        (_, Some(error)) => panic!("{:?} failed with {:?}", source, error),
+    }
+}
+
+// what `source` printed before the runtime error it must end in, and the error
+fn run_error(source: &str) -> (String, String) {
+    match run_with_input(source, "") {
+        (output, Some(error)) => (output, error),
+        (output, None) => panic!("{:?} ran without errors, printing {:?}", source, output),
+    }
+}
+
+// what a line typed at the prompt printed, echoed values included
+fn repl(source: &str) -> String {
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    let (statements, errors) = Parser::new(source, tokens).parse_repl_line();
+    assert!(errors.is_empty(), "{:?} parsed with errors {:?}", source, errors);
+    match interpret(&statements, "") {
+        (output, None) => output,
+        (_, Some(error)) => panic!("{:?} failed with {:?}", source, error),
+    }
+}
+
+// the value of a lone expression, or its runtime error as `line:col message`
+fn eval(source: &str) -> Result<Value, String> {
+    eval_with(Interpreter::with_io(Box::new(io::empty()), Box::new(io::sink())), source)
+}
+
+fn eval_with(mut interpreter: Interpreter, source: &str) -> Result<Value, String> {
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    let (expr, errors) = Parser::new(source, tokens).parse_expression();
+    assert!(errors.is_empty(), "{:?} parsed with errors {:?}", source, errors);
+    interpreter.evaluate(&expr.unwrap()).map_err(|error| message(&error))
+}
+
+#[test]
+fn expressions_evaluate_to_their_values() {
+    let cases: &[(&str, Value)] = &[
+        ("12.5", Value::from(12.5)),
+        ("\"str\"", Value::from("str".to_string())),
+        ("true", Value::from(true)),
+        ("nil", Value::Nil),
+        ("(1)", Value::from(1.0)),
+        ("-3", Value::from(-3.0)),
+        ("!true", Value::from(false)),
+        ("!nil", Value::from(true)),
+        ("1 + 2", Value::from(3.0)),
+        ("\"a\" + \"b\"", Value::from("ab".to_string())),
+        ("5 - 7", Value::from(-2.0)),
+        ("2 * 3", Value::from(6.0)),
+        ("7 / 2", Value::from(3.5)),
+        ("2 > 1", Value::from(true)),
+        ("1 >= 1", Value::from(true)),
+        ("2 < 1", Value::from(false)),
+        ("2 <= 1", Value::from(false)),
+        ("1 == 1", Value::from(true)),
+        ("1 == \"1\"", Value::from(false)),
+        ("nil == nil", Value::from(true)),
+        ("1 != 2", Value::from(true)),
+        ("\"a\" != \"a\"", Value::from(false)),
+        ("(1 + 2) * 3 - -1", Value::from(10.0)),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(eval(source).as_ref(), Ok(expected), "{:?}", source);
+    }
+}
+
+#[test]
+fn type_errors_point_at_the_operator() {
+    let cases = [
+        ("-\"abc\"", "1:0 Operand must be a number"),
+        ("1 + nil", "1:2 Operands must be two numbers or two strings"),
+        ("\"a\" + 1", "1:4 Operands must be two numbers or two strings"),
+        ("true - 1", "1:5 Operands must be numbers"),
+        ("1 * \"2\"", "1:2 Operands must be numbers"),
+        ("nil / 2", "1:4 Operands must be numbers"),
+        ("3 < \"x\"", "1:2 Operands must be numbers"),
+        ("3 <= nil", "1:2 Operands must be numbers"),
+        ("\"a\" > \"b\"", "1:4 Operands must be numbers"),
+        ("false >= 1", "1:6 Operands must be numbers"),
+        ("1 / 0", "1:2 Division by zero"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(eval(source), Err(expected.to_string()), "{:?}", source);
+    }
+}
+
+#[test]
+fn print_formats_values_the_lox_way() {
+    assert_eq!(run("print 7.0; print -0; print 0.5; print 1e21; print nil; print true;"), "7\n-0\n0.5\n1e21\nnil\ntrue\n");
+}
+
+#[test]
+fn print_shows_strings_without_quotes_and_the_repl_with_them() {
+    assert_eq!(run("print \"a b\";"), "a b\n");
+    assert_eq!(repl("\"a b\""), "\"a b\"\n");
+    assert_eq!(repl("1 + 1"), "2\n");
+}
+
+#[test]
+fn zero_and_the_empty_string_are_truthy() {
+    assert_eq!(run("if (0) print \"truthy\"; else print \"falsey\";"), "truthy\n");
+    assert_eq!(run("if (\"\") print \"truthy\";"), "truthy\n");
+    assert_eq!(run("if (nil) print \"truthy\"; else print \"falsey\";"), "falsey\n");
+    assert_eq!(run("print !0; print !nil; print 0 and \"and\"; print nil or \"or\";"), "false\ntrue\nand\nor\n");
+    assert_eq!(run("var i = 0; while (i) { print i; i = false; }"), "0\n");
+}
+
+#[test]
+fn no_coercion_in_equality() {
+    assert_eq!(run("print \"1\" == 1; print \"1\" != 1; print nil == false; print 0 == -0;"), "false\ntrue\nfalse\ntrue\n");
+}
+
+#[test]
+fn runtime_error_stops_the_run() {
+    assert_eq!(run_error("print 1;\nprint -nil;\nprint 2;"), ("1\n".to_string(), "2:6 Operand must be a number".to_string()));
+}
+
+#[test]
+fn plus_concatenates_strings() {
+    assert_eq!(eval("\"\" + \"\""), Ok(Value::from(String::new())));
+    assert_eq!(eval("\"foo\" + \"bar\""), Ok(Value::from("foobar".to_string())));
+    assert_eq!(eval("\"a\" + \"b\" + \"c\" + \"d\" + \"e\" + \"f\""), Ok(Value::from("abcdef".to_string())));
+    assert_eq!(run("var s = \"x\"; var t = s + \"y\"; print s; print t;"), "x\nxy\n");
+}
+
+#[test]
+fn plus_on_a_string_and_a_number_is_an_error_at_the_plus() {
+    assert_eq!(eval("\"a\" +\n 1"), Err("1:4 Operands must be two numbers or two strings".to_string()));
+    assert_eq!(eval("1 + \"a\""), Err("1:2 Operands must be two numbers or two strings".to_string()));
+}
+
+#[test]
+fn division_by_zero_is_an_error_by_default() {
+    assert_eq!(eval("1 / 0"), Err("1:2 Division by zero".to_string()));
+    assert_eq!(eval("0 / 0"), Err("1:2 Division by zero".to_string()));
+    assert_eq!(eval("1 / -0"), Err("1:2 Division by zero".to_string()));
+}
+
+#[test]
+fn ieee_division_gives_inf_and_nan() {
+    let ieee = || {
+        let mut interpreter = Interpreter::with_io(Box::new(io::empty()), Box::new(io::sink()));
+        interpreter.ieee_division = true;
+        interpreter
+    };
+    assert_eq!(eval_with(ieee(), "1 / 0"), Ok(Value::from(f64::INFINITY)));
+    assert_eq!(eval_with(ieee(), "-1 / 0"), Ok(Value::from(f64::NEG_INFINITY)));
+    assert!(matches!(eval_with(ieee(), "0 / 0"), Ok(Value::Number(number)) if number.is_nan()));
+}
+
+#[test]
+fn print_writes_each_value_on_its_own_line() {
+    let output = run("print \"one\";\nprint 1 + 1;\nprint nil;\nprint \"a\" + \"b\";");
+    assert_eq!(output.lines().collect::<Vec<_>>(), ["one", "2", "nil", "ab"]);
+}
+
+#[test]
+fn expression_statement_runs_once_and_prints_nothing() {
+    assert_eq!(run("var calls = 0;\nfun f() { calls = calls + 1; return calls; }\nf();\nprint calls;"), "1\n");
+    assert_eq!(repl("1 + 1;"), "");
+}
+
+#[test]
+fn error_in_an_expression_statement_stops_the_run() {
+    assert_eq!(run_error("print 1;\n\"a\" - 1;\nprint 2;"), ("1\n".to_string(), "2:4 Operands must be numbers".to_string()));
+}
+
+#[test]
+fn var_defines_with_nil_by_default() {
+    assert_eq!(run("var a; print a; var b = 2; print b;"), "nil\n2\n");
+}
+
+#[test]
+fn assignment_changes_a_defined_variable() {
+    assert_eq!(run("var a = 1; a = 2; print a; print a = 3;"), "2\n3\n");
+}
+
+#[test]
+fn var_redefines_a_global() {
+    assert_eq!(run("var a = 1; var a = \"two\"; print a;"), "two\n");
+}
+
+#[test]
+fn use_before_define_is_an_error_at_the_name() {
+    assert_eq!(run_error("print 1;\nprint  missing;"), ("1\n".to_string(), "2:7 Undefined variable 'missing'".to_string()));
+}
+
+#[test]
+fn assignment_doesnt_create_a_global() {
+    assert_eq!(run_error("fresh = 1;"), (String::new(), "1:0 Undefined variable 'fresh'".to_string()));
+}
+
+#[test]
+fn nested_scopes_shadow_and_restore() {
+    let source = r#"
+var a = "global a";
+var b = "global b";
+var c = "global c";
+{
+  var a = "outer a";
+  var b = "outer b";
+  {
+    var a = "inner a";
+    print a;
+    print b;
+    print c;
+  }
+  print a;
+  print b;
+  print c;
+}
+print a;
+print b;
+print c;
+"#;
+    assert_eq!(run(source), "inner a\nouter b\nglobal c\nouter a\nouter b\nglobal c\nglobal a\nglobal b\nglobal c\n");
+}
+
+#[test]
+fn block_scope_is_dropped_when_an_error_unwinds_it() {
+    let parse = |source| {
+        let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+        Parser::new(source, tokens).parse().0
+    };
+    let output = Output::default();
+    let mut interpreter = Interpreter::with_io(Box::new(io::empty()), Box::new(output.clone()));
+    // like two lines typed at the prompt, the second run in whatever scope the first left behind
+    assert!(interpreter.interpret(&parse("var a = \"global\"; { var a = \"block\"; nil - 1; }")).is_err());
+    interpreter.interpret(&parse("print a;")).unwrap();
+    assert_eq!(output.text(), "global\n");
+}
+
+#[test]
+fn only_the_taken_branch_runs() {
+    assert_eq!(run("if (true) print \"then\"; else print \"else\";"), "then\n");
+    assert_eq!(run("if (false) print \"then\"; else print \"else\";"), "else\n");
+    assert_eq!(run("if (false) print \"then\";"), "");
+}
+
+#[test]
+fn error_in_the_condition_runs_neither_branch() {
+    assert_eq!(
+        run_error("if (-\"x\") print \"then\"; else print \"else\";"),
+        (String::new(), "1:4 Operand must be a number".to_string())
+    );
+}
+
+#[test]
+fn else_if_chains() {
+    let source = r#"
+fun grade(score) {
+  if (score >= 90) print "A";
+  else if (score >= 80) print "B";
+  else if (score >= 70) {
+    if (score >= 75) print "C+"; else print "C";
+  }
+  else print "F";
+}
+grade(95);
+grade(85);
+grade(77);
+grade(70);
+grade(10);
+"#;
+    assert_eq!(run(source), "A\nB\nC+\nC\nF\n");
+}
+
+#[test]
+fn while_counts_to_five() {
+    assert_eq!(run("var i = 1; while (i <= 5) { print i; i = i + 1; }"), "1\n2\n3\n4\n5\n");
+}
+
+#[test]
+fn while_with_a_false_condition_never_runs() {
+    assert_eq!(run("while (false) print \"never\"; print \"after\";"), "after\n");
+}
+
+#[test]
+fn while_body_can_end_the_loop() {
+    assert_eq!(run("var go = true; while (go) { print \"once\"; go = false; }"), "once\n");
+}
+
+#[test]
+fn while_stops_at_an_error_in_the_condition_or_body() {
+    assert_eq!(
+        run_error("var i = 0; while (i < 3) { print i; i = i + 1; if (i == 2) i = nil; }"),
+        ("0\n1\n".to_string(), "1:20 Operands must be numbers".to_string())
+    );
+    assert_eq!(
+        run_error("var i = 0; while (true) { print i; i = i + 1; if (i == 2) -nil; }"),
+        ("0\n1\n".to_string(), "1:58 Operand must be a number".to_string())
+    );
+}
+
+#[test]
+fn long_loop_runs_without_growing_the_stack() {
+    assert_eq!(run("var i = 0; while (i < 100000) i = i + 1; print i;"), "100000\n");
+}
+
+#[test]
+fn for_prints_fibonacci() {
+    let source = "var a = 0;\nvar temp;\nfor (var b = 1; a < 100; b = temp + b) {\n  print a;\n  temp = a;\n  a = b;\n}";
+    assert_eq!(run(source), "0\n1\n1\n2\n3\n5\n8\n13\n21\n34\n55\n89\n");
+}
+
+#[test]
+fn for_without_an_initializer_or_increment() {
+    assert_eq!(run("var i = 0; for (; i < 3; i = i + 1) print i;"), "0\n1\n2\n");
+    assert_eq!(run("for (var i = 0; i < 3;) { print i; i = i + 1; }"), "0\n1\n2\n");
+}
+
+#[test]
+fn for_variable_stays_in_the_loop() {
+    assert_eq!(run_error("for (var i = 0; i < 1; i = i + 1) {}\nprint i;"), (String::new(), "2:6 Undefined variable 'i'".to_string()));
+    assert_eq!(run("for (var i = 0; i < 2; i = i + 1) print i;\nfor (var i = 5; i < 7; i = i + 1) print i;"), "0\n1\n5\n6\n");
+}
+
+#[test]
+fn for_runs_the_increment_after_continue() {
+    assert_eq!(run("for (var i = 0; i < 5; i = i + 1) { if (i == 2) continue; print i; }"), "0\n1\n3\n4\n");
+}
+
+#[test]
+fn logical_operators_return_an_operand() {
+    assert_eq!(run("print \"hi\" or 2; print nil or \"fallback\"; print nil and 1; print 1 and \"both\";"), "hi\nfallback\nnil\nboth\n");
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    // the right side would fail, or assign, if it ran
+    assert_eq!(run("print true or -nil; print false and undefined();"), "true\nfalse\n");
+    assert_eq!(run("var a = 1; true or (a = 2); nil and (a = 3); print a;"), "1\n");
+    assert_eq!(run("var a = 1; false or (a = 2); true and (a = a + 1); print a;"), "3\n");
+}
+
+#[test]
+fn two_argument_function() {
+    assert_eq!(run("fun add(a, b) { print a + b; }\nadd(1, 2);\nprint add;"), "3\n<fn add>\n");
+}
+
+#[test]
+fn calling_a_number_is_an_error() {
+    assert_eq!(run_error("var n = 1;\nn(2);"), (String::new(), "2:3 Can only call functions and classes".to_string()));
+}
+
+#[test]
+fn wrong_arity_is_an_error() {
+    assert_eq!(run_error("fun f(a, b) {}\nf(1);"), (String::new(), "2:3 Expected 2 arguments but got 1".to_string()));
+}
+
+#[test]
+fn recursive_count() {
+    assert_eq!(run("fun count(n) {\n  if (n > 1) count(n - 1);\n  print n;\n}\ncount(3);"), "1\n2\n3\n");
+}
+
+#[test]
+fn call_stack_limit_is_a_runtime_error() {
+    let source = "fun r() { r(); }\nr();";
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    let (statements, _) = Parser::new(source, tokens).parse();
+    let mut interpreter = Interpreter::with_io(Box::new(io::empty()), Box::new(io::sink()));
+    interpreter.max_call_stack = 64 * 1024;
+    assert_eq!(interpreter.interpret(&statements).map_err(|error| message(&error)), Err("1:12 Stack overflow".to_string()));
+}
+
+#[test]
+fn return_leaves_a_while_loop() {
+    let source = "fun first_over(limit) {\n  var i = 0;\n  while (true) {\n    i = i + 1;\n    if (i * i > limit) return i;\n  }\n}\nprint first_over(50);";
+    assert_eq!(run(source), "8\n");
+}
+
+#[test]
+fn return_from_an_if_in_a_block() {
+    let source = "fun sign(n) {\n  {\n    if (n < 0) { return \"-\"; }\n  }\n  if (n == 0) return;\n  return \"+\";\n}\nprint sign(-3); print sign(0); print sign(2);";
+    assert_eq!(run(source), "-\nnil\n+\n");
+}
+
+#[test]
+fn recursive_fibonacci() {
+    let source = "fun fib(n) {\n  if (n <= 1) return n;\n  return fib(n - 2) + fib(n - 1);\n}\nfor (var i = 0; i < 10; i = i + 1) print fib(i);";
+    assert_eq!(run(source), "0\n1\n1\n2\n3\n5\n8\n13\n21\n34\n");
+}
+
+#[test]
+fn clock_never_goes_back() {
+    assert_eq!(run("var start = clock(); var i = 0; while (i < 1000) i = i + 1; print clock() >= start; print clock() > 0;"), "true\ntrue\n");
+}
+
+#[test]
+fn clock_is_a_native_with_an_arity() {
+    assert_eq!(run("print clock;"), "<native fn>\n");
+    assert_eq!(run_error("clock(1);"), (String::new(), "1:7 Expected 0 arguments but got 1".to_string()));
+}
+
+#[test]
+fn guess_the_number_against_scripted_input() {
+    let source = r#"
+var secret = 42;
+var guesses = 0;
+var guess = readNumber();
+while (guess != nil and guess != secret) {
+  guesses = guesses + 1;
+  if (guess < secret) print "higher"; else print "lower";
+  guess = readNumber();
+}
+if (guess == secret) print "got it in " + str(guesses + 1);
+else print "gave up";
+"#;
+    assert_eq!(run_with_input(source, "50\n10\n42\n"), ("lower\nhigher\ngot it in 3\n".to_string(), None));
+    assert_eq!(run_with_input(source, "1\r\nnot a number\n"), ("higher\ngave up\n".to_string(), None));
+}
+
+#[test]
+fn read_line_gives_lines_then_nil() {
+    let source = "print readLine(); print readLine(); print readLine() == nil;";
+    assert_eq!(run_with_input(source, "first\r\n  second  "), ("first\n  second  \ntrue\n".to_string(), None));
+}
+
+#[test]
+fn len_counts_characters() {
+    assert_eq!(run("print len(\"\"); print len(\"héllo\"); print len(\"a\" + \"b\");"), "0\n5\n2\n");
+}
+
+#[test]
+fn len_of_a_number_is_an_error() {
+    assert_eq!(run_error("print len(42);"), (String::new(), "1:12 Argument must be a string".to_string()));
+}
+
+#[test]
+fn str_is_what_print_shows() {
+    assert_eq!(run("print str(nil) + str(7) + str(0.5) + str(true) + str(\"s\"); print len(str(clock)) > 0;"), "nil70.5trues\ntrue\n");
+}
+
+#[test]
+fn num_parses_or_gives_nil() {
+    let source = r#"print num("12"); print num(" 1 "); print num("-0.5"); print num(""); print num("1x"); print num("1e3");
+print num(3); print num(nil);"#;
+    assert_eq!(run(source), "12\n1\n-0.5\nnil\nnil\nnil\n3\nnil\n");
+}
+
+#[test]
+fn classes_and_instances_print_their_names() {
+    assert_eq!(run("class Bagel {}\nprint Bagel;\nprint Bagel();"), "Bagel\nBagel instance\n");
+}
+
+#[test]
+fn fields_are_created_by_setting_them() {
+    assert_eq!(run("class Point {}\nvar p = Point();\np.x = 1;\np.y = 2;\nprint p.x + p.y;\np.x = 10;\nprint p.x;"), "3\n10\n");
+}
+
+#[test]
+fn missing_field_is_an_error_at_its_name() {
+    assert_eq!(run_error("class Point {}\nvar p = Point();\nprint p.z;"), (String::new(), "3:8 Undefined property 'z'".to_string()));
+}
+
+#[test]
+fn instances_nest_and_are_shared() {
+    let source = "class Box {}\nvar outer = Box();\nvar inner = Box();\nouter.inner = inner;\ninner.value = \"v\";\nprint outer.inner.value;\nouter.inner.value = \"w\";\nprint inner.value;";
+    assert_eq!(run(source), "v\nw\n");
+}
+
+#[test]
+fn only_instances_have_fields() {
+    assert_eq!(run_error("var n = 1;\nn.x = 2;"), (String::new(), "2:2 Only instances have fields".to_string()));
+}
+
+#[test]
+fn bound_method_keeps_its_instance() {
+    let source = r#"
+class Person {
+  sayName() {
+    print this.name;
+  }
+}
+
+var jane = Person();
+jane.name = "Jane";
+
+var bill = Person();
+bill.name = "Bill";
+
+bill.sayName = jane.sayName;
+bill.sayName();
+"#;
+    assert_eq!(run(source), "Jane\n");
+}
+
+#[test]
+fn method_taken_off_an_instance_still_sees_this() {
+    let source = "class Counter {\n  inc() { this.n = this.n + 1; return this.n; }\n}\nvar c = Counter();\nc.n = 0;\nvar m = c.inc;\nm();\nprint m();\nprint c.n;";
+    assert_eq!(run(source), "2\n2\n");
+}
+
+#[test]
+fn fields_shadow_methods() {
+    assert_eq!(run("class A { f() { return \"method\"; } }\nvar a = A();\nprint a.f();\na.f = \"field\";\nprint a.f;"), "method\nfield\n");
+}
+
+#[test]
+fn unbounded_recursion_on_an_ordinary_thread_is_a_runtime_error() {
+    // the default limit fits the test thread's stack, without the big thread the CLI runs on
+    assert_eq!(run_error("fun r(n) { return r(n + 1); }\nr(0);"), (String::new(), "1:25 Stack overflow".to_string()));
+    assert_eq!(
+        run_error("fun d(n) { if (n > -1e9) { while (true) { return 1 + (2 * (d(n - 1) + 1)); } } }\nd(0);"),
+        (String::new(), "1:66 Stack overflow".to_string())
+    );
+}
+
+#[test]
+fn interpreters_on_stdin_can_be_made_one_after_the_other() {
+    // neither holds stdin's lock, so the second doesn't wait on the first
+    let first = Lox::new();
+    let mut second = Lox::new();
+    second.run("fun r(n) { return r(n + 1); }\nr(0);");
+    assert!(!second.has_error);
+    assert!(second.has_runtime_error);
+    drop(first);
+    assert!(eval_with(Interpreter::new(), "1 + 2").is_ok());
+}
@@ -0,0 +1,782 @@
+use interpreter::lox::{
+    ast::{
+        printer::{print, print_stmt, rpn},
+        Expr, ExprKind, Stmt, StmtKind,
+    },
+    error::ParseError,
+    parser::Parser,
+    scanner::Scanner,
+    tokens::{Span, TokenType},
+};
+
+fn messages(errors: &[ParseError]) -> Vec<String> {
+    errors.iter().map(|error| format!("{}:{} {}", error.span.line, error.span.col, error.message)).collect()
+}
+
+// a lone expression, which must parse without errors
+fn parse_expr(source: &str) -> Expr<'_> {
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    match Parser::new(source, tokens).parse_expression() {
+        (Some(expr), errors) if errors.is_empty() => expr,
+        (_, errors) => panic!("{:?} parsed with errors {:?}", source, messages(&errors)),
+    }
+}
+
+// the tree of a lone expression, as `--print-ast` shows it
+fn tree(source: &str) -> String {
+    print(&parse_expr(source))
+}
+
+fn expr_errors(source: &str) -> Vec<String> {
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    messages(&Parser::new(source, tokens).parse_expression().1)
+}
+
+// a script's statements and its errors as `line:col message`
+fn parse(source: &str) -> (Vec<Stmt<'_>>, Vec<String>) {
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    let (statements, errors) = Parser::new(source, tokens).parse();
+    (statements, messages(&errors))
+}
+
+fn parse_errors(source: &str) -> Vec<String> {
+    parse(source).1
+}
+
+// the trees of a line typed at the prompt, which must parse without errors
+fn repl_trees(source: &str) -> Vec<String> {
+    let tokens = Scanner::new(source).scan_tokens().unwrap().to_vec();
+    match Parser::new(source, tokens).parse_repl_line() {
+        (statements, errors) if errors.is_empty() => statements.iter().map(print_stmt).collect(),
+        (_, errors) => panic!("{:?} parsed with errors {:?}", source, messages(&errors)),
+    }
+}
+
+// the trees of a script's statements, which must parse without errors
+fn trees(source: &str) -> Vec<String> {
+    match parse(source) {
+        (statements, errors) if errors.is_empty() => statements.iter().map(print_stmt).collect(),
+        (_, errors) => panic!("{:?} parsed with errors {:?}", source, errors),
+    }
+}
+
+#[test]
+fn factor_binds_tighter_than_term() {
+    assert_eq!(tree("1 + 2 * 3"), "(+ 1 (* 2 3))");
+    assert_eq!(tree("1 * 2 + 3"), "(+ (* 1 2) 3)");
+}
+
+#[test]
+fn term_binds_tighter_than_comparison() {
+    assert_eq!(tree("1 + 2 < 3 * 4"), "(< (+ 1 2) (* 3 4))");
+}
+
+#[test]
+fn comparison_binds_tighter_than_equality() {
+    assert_eq!(tree("a == b < c"), "(== a (< b c))");
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_eq!(tree("a or b and c"), "(or a (and b c))");
+}
+
+#[test]
+fn or_binds_tighter_than_assignment() {
+    assert_eq!(tree("a = b or c"), "(= a (or b c))");
+}
+
+#[test]
+fn comma_is_the_loosest() {
+    assert_eq!(tree("a, b = c"), "(, a (= b c))");
+    assert_eq!(tree("a ? b, c : d"), "(?: a (, b c) d)");
+}
+
+#[test]
+fn unary_binds_tighter_than_binary() {
+    assert_eq!(tree("-a * b"), "(* (- a) b)");
+    assert_eq!(tree("!a == b"), "(== (! a) b)");
+}
+
+#[test]
+fn binary_operators_are_left_associative() {
+    assert_eq!(tree("1 - 2 - 3"), "(- (- 1 2) 3)");
+    assert_eq!(tree("8 / 4 / 2"), "(/ (/ 8 4) 2)");
+    assert_eq!(tree("a, b, c"), "(, (, a b) c)");
+}
+
+#[test]
+fn assignment_is_right_associative() {
+    assert_eq!(tree("a = b = c"), "(= a (= b c))");
+}
+
+#[test]
+fn conditional_is_right_associative() {
+    assert_eq!(tree("a ? b : c ? d : e"), "(?: a b (?: c d e))");
+}
+
+#[test]
+fn grouping_overrides_precedence() {
+    assert_eq!(tree("(1 + 2) * 3"), "(* (group (+ 1 2)) 3)");
+}
+
+#[test]
+fn groupings_nest() {
+    assert_eq!(tree("((a))"), "(group (group a))");
+}
+
+#[test]
+fn grouping_is_a_node_of_its_own() {
+    assert_eq!(parse_expr("1 + 2"), parse_expr("1 + 2"));
+    assert_ne!(parse_expr("(1)"), parse_expr("1"));
+}
+
+#[test]
+fn negation() {
+    assert_eq!(tree("-1"), "(- 1)");
+}
+
+#[test]
+fn unary_operators_nest() {
+    assert_eq!(tree("!!true"), "(! (! true))");
+    assert_eq!(tree("- -a"), "(- (- a))");
+}
+
+#[test]
+fn double_minus_prefix_is_two_negations() {
+    assert_eq!(tree("--1"), "(- (- 1))");
+    assert_eq!(tree("--1"), tree("- -1"));
+}
+
+#[test]
+fn keywords_as_literals() {
+    assert_eq!(tree("true"), "true");
+    assert_eq!(tree("false"), "false");
+    assert_eq!(tree("nil"), "nil");
+}
+
+#[test]
+fn number_and_string_literals() {
+    assert_eq!(tree("12.5"), "12.5");
+    assert_eq!(tree("\"str\""), "\"str\"");
+}
+
+#[test]
+fn calls_chain_left_to_right() {
+    assert_eq!(tree("f(1, 2)(3)"), "(call (call f 1 2) 3)");
+}
+
+#[test]
+fn property_reads_chain_left_to_right() {
+    assert_eq!(tree("a.b.c"), "(. (. a b) c)");
+}
+
+#[test]
+fn assignment_targets() {
+    assert_eq!(tree("a.b = c"), "(= (. a b) c)");
+    assert_eq!(tree("a[1] = 2"), "(= (index a 1) 2)");
+}
+
+#[test]
+fn spans_cover_the_whole_expression() {
+    assert_eq!(parse_expr(" 1 + 2 * 3").span(), Span::new(1, 1, 9));
+    assert_eq!(parse_expr("(a)\n  .b").span(), Span::new(1, 0, 8));
+}
+
+#[test]
+fn missing_operand_is_reported_after_the_operator() {
+    assert_eq!(expr_errors("1 +"), ["1:3 Expected expression at end of input"]);
+}
+
+#[test]
+fn unclosed_grouping() {
+    assert_eq!(expr_errors("(1 + 2"), ["1:6 Expected ')' after expression at end of input"]);
+}
+
+#[test]
+fn stray_closing_paren() {
+    assert_eq!(expr_errors(")"), ["1:0 Unmatched ')'"]);
+}
+
+#[test]
+fn invalid_assignment_target_is_reported_at_the_equals() {
+    assert_eq!(expr_errors("1 = 2"), ["1:2 Invalid assignment target"]);
+}
+
+#[test]
+fn conditional_without_a_condition() {
+    assert_eq!(expr_errors("? a : b"), ["1:0 Expected a condition before '?'"]);
+}
+
+#[test]
+fn conditional_without_an_else_branch() {
+    assert_eq!(expr_errors("a ? b"), ["1:5 Expected ':' after the then branch of a conditional at end of input"]);
+}
+
+#[test]
+fn property_name_must_be_an_identifier() {
+    assert_eq!(expr_errors("a.(b)"), ["1:2 Expected property name after '.'"]);
+}
+
+#[test]
+fn trailing_tokens_after_an_expression() {
+    assert_eq!(expr_errors("1 2"), ["1:2 Expected end of expression"]);
+}
+
+#[test]
+fn empty_call_argument() {
+    assert_eq!(expr_errors("f(1,)"), ["1:4 Expected expression"]);
+}
+
+#[test]
+fn nesting_within_the_limit_parses() {
+    let source = format!("print {}1{};", "(".repeat(100), ")".repeat(100));
+    assert_eq!(parse_errors(&source), Vec::<String>::new());
+    let source = format!("{}print 1;{}", "{".repeat(100), "}".repeat(100));
+    assert_eq!(parse_errors(&source), Vec::<String>::new());
+}
+
+#[test]
+fn deeply_nested_parentheses_are_one_error() {
+    let source = format!("print {}1{};", "(".repeat(500), ")".repeat(500));
+    assert_eq!(parse_errors(&source), ["1:133 Expression nested too deeply"]);
+}
+
+#[test]
+fn deeply_nested_unary_operators_are_one_error() {
+    let source = format!("print {}1;", "! ".repeat(500));
+    assert_eq!(parse_errors(&source), ["1:258 Expression nested too deeply"]);
+}
+
+#[test]
+fn deeply_nested_calls_are_one_error() {
+    let source = format!("{}1{};", "f(".repeat(500), ")".repeat(500));
+    assert_eq!(parse_errors(&source), ["1:254 Expression nested too deeply"]);
+}
+
+#[test]
+fn deeply_nested_blocks_are_one_error() {
+    let source = format!("{}print 1;{}\nprint 2;", "{".repeat(1000), "}".repeat(1000));
+    assert_eq!(parse_errors(&source), ["1:128 Statement nested too deeply"]);
+}
+
+#[test]
+fn deeply_nested_functions_are_one_error() {
+    let source = format!("{}print 1;{}", "fun f() { ".repeat(500), "}".repeat(500));
+    assert_eq!(parse_errors(&source), ["1:1280 Statement nested too deeply"]);
+}
+
+#[test]
+fn three_statement_program() {
+    assert_eq!(trees("var a = 1;\nprint a + 2;\na = 3;"), ["(var a 1)", "(print (+ a 2))", "(expr (= a 3))"]);
+}
+
+#[test]
+fn missing_semicolon_is_reported_after_the_statement() {
+    assert_eq!(parse_errors("var a = 1;\nprint a\na = 3;"), ["2:7 Expected ';' after value"]);
+}
+
+#[test]
+fn switch_arms_end_at_the_next_label() {
+    assert_eq!(
+        trees("switch (x) { case 1: print 1; print 2; case 2: default: print 3; }"),
+        ["(switch x (case 1 (print 1) (print 2)) (case 2) (default (print 3)))"]
+    );
+    assert_eq!(trees("switch (x) {}"), ["(switch x)"]);
+}
+
+#[test]
+fn switch_without_a_scrutinee() {
+    assert_eq!(parse_errors("switch () { default: print 1; }"), ["1:8 Expected a value to switch on"]);
+}
+
+#[test]
+fn case_outside_a_switch() {
+    assert_eq!(parse_errors("print 1;\ncase 2: print 2;"), ["2:0 Found 'case' outside a switch"]);
+    assert_eq!(parse_errors("{ default: print 2; }"), ["1:2 Found 'default' outside a switch"]);
+}
+
+#[test]
+fn switch_with_two_defaults() {
+    assert_eq!(
+        parse_errors("switch (x) {\n  default: print 1;\n  default: print 2;\n}"),
+        ["3:2 A switch can only have one 'default'"]
+    );
+}
+
+#[test]
+fn repl_line_can_be_a_bare_expression() {
+    assert_eq!(repl_trees("1+2"), ["(echo (+ 1 2))"]);
+}
+
+#[test]
+fn repl_line_can_be_statements() {
+    assert_eq!(repl_trees("var a = 3;"), ["(var a 3)"]);
+    assert_eq!(repl_trees("print a;"), ["(print a)"]);
+    assert_eq!(repl_trees("a; b;"), ["(expr a)", "(expr b)"]);
+}
+
+#[test]
+fn repl_line_that_is_neither_keeps_the_statement_errors() {
+    let tokens = Scanner::new("print a").scan_tokens().unwrap().to_vec();
+    let (statements, errors) = Parser::new("print a", tokens).parse_repl_line();
+    assert!(statements.is_empty());
+    assert_eq!(messages(&errors), ["1:7 Expected ';' after value at end of input"]);
+}
+
+#[test]
+fn script_still_needs_the_semicolon() {
+    assert_eq!(parse_errors("1+2"), ["1:3 Expected ';' after expression at end of input"]);
+}
+
+#[test]
+fn return_at_top_level() {
+    assert_eq!(parse_errors("print 1;\n{ return 2; }"), ["2:2 Can't return from top-level code"]);
+}
+
+#[test]
+fn unary_operands_bind_before_binary_ones() {
+    assert_eq!(tree("-1 + 2"), "(+ (- 1) 2)");
+    assert_eq!(tree("!(a == b)"), "(! (group (== a b)))");
+}
+
+#[test]
+fn unary_node_keeps_its_operator_and_span() {
+    let expr = parse_expr(" !(a == b)");
+    assert_eq!(expr.span(), Span::new(1, 1, 9));
+    match expr.kind {
+        ExprKind::Unary { operator, right } => {
+            assert_eq!((operator.ttype(), operator.span()), (TokenType::Bang, Span::new(1, 1, 1)));
+            assert_eq!(right.span(), Span::new(1, 2, 8));
+        }
+        kind => panic!("not a unary: {:?}", kind),
+    }
+}
+
+#[test]
+fn printer_example() {
+    assert_eq!(tree("-123 * (45.67)"), "(* (- 123) (group 45.67))");
+}
+
+#[test]
+fn printed_statements() {
+    assert_eq!(
+        trees("var a = 1;\nprint a * 2;\n{ a = nil; }\nif (a) print \"yes\"; else a;\nwhile (!a) print a;"),
+        [
+            "(var a 1)",
+            "(print (* a 2))",
+            "(block (expr (= a nil)))",
+            "(if a (print \"yes\") (expr a))",
+            "(while (! a) (print a))",
+        ]
+    );
+}
+
+#[test]
+fn rpn_drops_groupings() {
+    assert_eq!(rpn(&parse_expr("(1 + 2) * (4 - 3)")), "1 2 + 4 3 - *");
+    assert_eq!(rpn(&parse_expr("((1 + 2) * 3) - 4")), "1 2 + 3 * 4 -");
+    assert_eq!(rpn(&parse_expr("1 + 2 * 3")), "1 2 3 * +");
+}
+
+#[test]
+fn rpn_tells_negation_from_subtraction() {
+    assert_eq!(rpn(&parse_expr("-a - b")), "a neg b -");
+    assert_eq!(rpn(&parse_expr("a - -b")), "a b neg -");
+    assert_eq!(rpn(&parse_expr("!-(1)")), "1 neg !");
+}
+
+#[test]
+fn conditional_binds_looser_than_equality_and_tighter_than_assignment() {
+    assert_eq!(tree("a == b ? c : d"), "(?: (== a b) c d)");
+    assert_eq!(tree("x = a ? b : c"), "(= x (?: a b c))");
+}
+
+#[test]
+fn commas_in_a_call_separate_arguments() {
+    let expr = parse_expr("f(a, b), c");
+    assert_eq!(print(&expr), "(, (call f a b) c)");
+    match expr.kind {
+        ExprKind::Binary { left, .. } => match left.kind {
+            ExprKind::Call { arguments, .. } => assert_eq!(arguments.len(), 2),
+            kind => panic!("not a call: {:?}", kind),
+        },
+        kind => panic!("not a comma: {:?}", kind),
+    }
+}
+
+#[test]
+fn errors_in_three_statements_are_all_reported() {
+    let (statements, errors) = parse("print 1 +; print 2; var = 3; print 4; print (5;\nprint 6;");
+    assert_eq!(errors, ["1:9 Expected expression", "1:24 Expected variable name", "1:46 Expected ')' after expression"]);
+    // the statements between the bad ones still parse
+    assert_eq!(statements.iter().map(print_stmt).collect::<Vec<_>>(), ["(print 2)", "(print 4)", "(print 6)"]);
+}
+
+#[test]
+fn variable_declarations() {
+    assert_eq!(trees("var x = 1;\nvar y;\nx + y;"), ["(var x 1)", "(var y)", "(expr (+ x y))"]);
+}
+
+#[test]
+fn variable_declaration_errors() {
+    assert_eq!(parse_errors("var ; print 1;"), ["1:4 Expected variable name"]);
+    assert_eq!(parse_errors("var x = 1\nprint x;"), ["1:9 Expected ';' after variable declaration"]);
+    assert_eq!(parse_errors("var class = 1; print 2;"), ["1:4 Can't use keyword 'class' as a variable name"]);
+    // the statement after each bad declaration still parses
+    assert_eq!(parse("var ; print 1;").0.iter().map(print_stmt).collect::<Vec<_>>(), ["(print 1)"]);
+}
+
+#[test]
+fn grouped_or_binary_targets_are_invalid() {
+    assert_eq!(parse_errors("(a) = 3;"), ["1:4 Invalid assignment target"]);
+    assert_eq!(parse_errors("a + b = 1;"), ["1:6 Invalid assignment target"]);
+}
+
+#[test]
+fn assignment_inside_a_grouping() {
+    assert_eq!(trees("(a = 3);\n(b = c = 4);"), ["(expr (group (= a 3)))", "(expr (group (= b (= c 4))))"]);
+}
+
+#[test]
+fn invalid_target_doesnt_enter_panic_mode() {
+    // the statement with the bad target is kept and the next ones parse normally
+    let (statements, errors) = parse("(a) = 3; print 1;\na + b = 1; print 2;");
+    assert_eq!(errors, ["1:4 Invalid assignment target", "2:6 Invalid assignment target"]);
+    assert_eq!(
+        statements.iter().map(print_stmt).collect::<Vec<_>>(),
+        ["(expr (group a))", "(print 1)", "(expr (+ a b))", "(print 2)"]
+    );
+}
+
+#[test]
+fn blocks_nest() {
+    assert_eq!(trees("{ { { print 1; } } }"), ["(block (block (block (print 1))))"]);
+}
+
+#[test]
+fn unclosed_block_is_reported_at_its_brace() {
+    assert_eq!(parse_errors("print 0;\n{ print 1;\n  print 2;"), ["2:0 Expected '}' to close this block"]);
+}
+
+#[test]
+fn last_statement_in_a_block_still_needs_its_semicolon() {
+    assert_eq!(parse_errors("{ print 1 }"), ["1:10 Expected ';' after value"]);
+}
+
+#[test]
+fn else_belongs_to_the_nearest_if() {
+    assert_eq!(trees("if (a) if (b) x(); else y();"), ["(if a (if b (expr (call x)) (expr (call y))))"]);
+}
+
+#[test]
+fn stray_else_is_reported_at_itself() {
+    let (statements, errors) = parse("print 0;\n  else print 1; print 2;");
+    assert_eq!(errors, ["2:2 Found 'else' without a matching 'if'"]);
+    assert_eq!(statements.iter().map(print_stmt).collect::<Vec<_>>(), ["(print 0)", "(print 1)", "(print 2)"]);
+}
+
+#[test]
+fn if_condition_needs_its_parentheses() {
+    assert_eq!(parse_errors("if a) print 1;"), ["1:3 Expected '(' after 'if'"]);
+    assert_eq!(parse_errors("if (a print 1;"), ["1:6 Expected ')' after if condition"]);
+}
+
+#[test]
+fn counted_while_loop() {
+    assert_eq!(trees("while (i < 3) i = i + 1;"), ["(while (< i 3) (expr (= i (+ i 1))))"]);
+}
+
+#[test]
+fn while_loops_nest() {
+    assert_eq!(trees("while (a) while (b) { print 1; }"), ["(while a (while b (block (print 1))))"]);
+}
+
+#[test]
+fn while_condition_needs_its_closing_paren() {
+    assert_eq!(parse_errors("while (a print 1;"), ["1:9 Expected ')' after while condition"]);
+}
+
+#[test]
+fn error_in_a_loop_body_keeps_the_statements_after_the_loop() {
+    let (statements, errors) = parse("while (a) { print 1 + ; } print 2;");
+    assert_eq!(errors, ["1:22 Expected expression"]);
+    assert_eq!(statements.iter().map(print_stmt).collect::<Vec<_>>(), ["(while a (block))", "(print 2)"]);
+}
+
+#[test]
+fn for_desugars_to_a_block_holding_a_while() {
+    let (statements, errors) = parse("for (var i = 0; i < 3; i = i + 1) print i;");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(print_stmt(&statements[0]), "(block (var i 0) (while (< i 3) (print i) (= i (+ i 1))))");
+    let StmtKind::Block(block) = &statements[0].kind else { panic!("not a block: {:?}", statements[0]) };
+    assert!(matches!(block[0].kind, StmtKind::Var { .. }));
+    match &block[1].kind {
+        StmtKind::While { increment: Some(increment), body, .. } => {
+            assert_eq!(print(increment), "(= i (+ i 1))");
+            assert_eq!(print_stmt(body), "(print i)");
+        }
+        kind => panic!("not a while with an increment: {:?}", kind),
+    }
+}
+
+#[test]
+fn for_with_no_clauses_loops_forever() {
+    assert_eq!(trees("for (;;) print 1;"), ["(while true (print 1))"]);
+}
+
+#[test]
+fn for_clauses_are_separated_by_semicolons() {
+    assert_eq!(parse_errors("for (var i = 0 i < 3; i = i + 1) print i;"), ["1:15 Expected ';' after variable declaration"]);
+    assert_eq!(parse_errors("for (var i = 0; i < 3 i = i + 1) print i;"), ["1:22 Expected ';' after loop condition"]);
+}
+
+#[test]
+fn function_without_parameters() {
+    assert_eq!(trees("fun f() { print 1; }"), ["(fun f () (block (print 1)))"]);
+}
+
+#[test]
+fn function_with_two_parameters() {
+    assert_eq!(trees("fun add(a, b) { return a + b; }"), ["(fun add (a b) (block (return (+ a b))))"]);
+}
+
+#[test]
+fn function_declared_inside_a_function() {
+    assert_eq!(
+        trees("fun outer() { fun inner(x) { return x; } return inner; }"),
+        ["(fun outer () (block (fun inner (x) (block (return x))) (return inner)))"]
+    );
+}
+
+#[test]
+fn too_many_parameters_is_reported_and_parsing_carries_on() {
+    let params: Vec<String> = (0..256).map(|i| format!("p{}", i)).collect();
+    let source = format!("fun f({}) {{ print 1; }}\nprint 2;", params.join(", "));
+    let (statements, errors) = parse(&source);
+    assert_eq!(errors, [format!("1:{} Can't have more than 255 parameters", source.find("p255").unwrap())]);
+    assert_eq!(statements.len(), 2);
+    assert!(print_stmt(&statements[0]).ends_with("p254 p255) (block (print 1)))"));
+    assert_eq!(print_stmt(&statements[1]), "(print 2)");
+}
+
+#[test]
+fn call_without_arguments() {
+    assert_eq!(tree("f()"), "(call f)");
+}
+
+#[test]
+fn call_keeps_its_closing_paren() {
+    match parse_expr("f(a,\n  b )").kind {
+        ExprKind::Call { paren, arguments, .. } => {
+            assert_eq!((paren.ttype(), paren.span()), (TokenType::RightParen, Span::new(2, 4, 1)));
+            assert_eq!(arguments.len(), 2);
+        }
+        kind => panic!("not a call: {:?}", kind),
+    }
+}
+
+#[test]
+fn unclosed_call_is_reported_where_the_paren_is_missing() {
+    let (statements, errors) = parse("f(1, 2;\nprint 3;");
+    assert_eq!(errors, ["1:6 Expected ')' after arguments"]);
+    assert_eq!(statements.iter().map(print_stmt).collect::<Vec<_>>(), ["(print 3)"]);
+}
+
+#[test]
+fn too_many_arguments_is_reported_and_parsing_carries_on() {
+    let source = format!("f({});\nprint 2;", vec!["1"; 256].join(", "));
+    let (statements, errors) = parse(&source);
+    assert_eq!(errors, [format!("1:{} Can't have more than 255 arguments", source.rfind('1').unwrap())]);
+    match &statements[0].kind {
+        StmtKind::Expression { expr, .. } => match &expr.kind {
+            ExprKind::Call { arguments, .. } => assert_eq!(arguments.len(), 256),
+            kind => panic!("not a call: {:?}", kind),
+        },
+        kind => panic!("not an expression statement: {:?}", kind),
+    }
+    assert_eq!(print_stmt(&statements[1]), "(print 2)");
+}
+
+#[test]
+fn return_with_and_without_a_value() {
+    assert_eq!(trees("fun f() { return; }"), ["(fun f () (block (return)))"]);
+    assert_eq!(trees("fun f() { return 1; }"), ["(fun f () (block (return 1)))"]);
+}
+
+#[test]
+fn bare_return_right_before_a_brace() {
+    assert_eq!(trees("fun f() { if (a) return }"), ["(fun f () (block (if a (return))))"]);
+}
+
+#[test]
+fn return_value_needs_its_semicolon() {
+    assert_eq!(parse_errors("fun f() { return 1 }"), ["1:19 Expected ';' after return value"]);
+}
+
+#[test]
+fn return_from_nested_blocks() {
+    assert_eq!(
+        trees("fun f() { { while (a) { return a; } } }"),
+        ["(fun f () (block (block (while a (block (return a))))))"]
+    );
+}
+
+#[test]
+fn empty_class() {
+    assert_eq!(trees("class A {}"), ["(class A)"]);
+}
+
+#[test]
+fn class_with_two_methods() {
+    assert_eq!(
+        trees("class A { one() { return 1; } two(x) { print x; } }"),
+        ["(class A (fun one () (block (return 1))) (fun two (x) (block (print x))))"]
+    );
+}
+
+#[test]
+fn class_with_a_superclass() {
+    assert_eq!(trees("class B < A {}"), ["(class B < A)"]);
+    // inheriting from itself is for the resolver or runtime to reject, it parses
+    assert_eq!(trees("class A < A {}"), ["(class A < A)"]);
+}
+
+#[test]
+fn class_errors() {
+    assert_eq!(parse_errors("class { }"), ["1:6 Expected class name"]);
+    assert_eq!(parse_errors("class B < { }"), ["1:10 Expected superclass name"]);
+    assert_eq!(parse_errors("class A { m() {}; }"), ["1:16 Unexpected ';' between methods"]);
+}
+
+#[test]
+fn nested_set_is_a_set_on_a_get() {
+    let expr = parse_expr("a.b.c = v");
+    assert_eq!(print(&expr), "(= (. (. a b) c) v)");
+    match expr.kind {
+        ExprKind::Set { object, name, .. } => {
+            assert_eq!(name.lexeme(), "c");
+            assert!(matches!(object.kind, ExprKind::Get { .. }), "{:?}", object);
+        }
+        kind => panic!("not a set: {:?}", kind),
+    }
+}
+
+#[test]
+fn field_of_a_call_can_be_assigned_but_not_the_call() {
+    assert_eq!(tree("f().x = 1"), "(= (. (call f) x) 1)");
+    assert_eq!(parse_errors("f() = 1;"), ["1:4 Invalid assignment target"]);
+}
+
+#[test]
+fn gets_and_calls_chain() {
+    assert_eq!(tree("a.b(1).c"), "(. (call (. a b) 1) c)");
+}
+
+#[test]
+fn this_in_a_method() {
+    assert_eq!(
+        trees("class A { m() { print this.x; return this; } }"),
+        ["(class A (fun m () (block (print (. this x)) (return this))))"]
+    );
+    assert_eq!(trees("class A { m() { this; } }"), ["(class A (fun m () (block (expr this))))"]);
+}
+
+#[test]
+fn this_cant_be_assigned() {
+    assert_eq!(parse_errors("class A { m() { this = 3; } }"), ["1:21 Invalid assignment target"]);
+}
+
+#[test]
+fn super_call_is_a_call_on_a_super_node() {
+    let (statements, errors) = parse("class B < A { init(x) { super.init(x); } }");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(print_stmt(&statements[0]), "(class B < A (fun init (x) (block (expr (call (super init) x)))))");
+    let StmtKind::Class { methods, .. } = &statements[0].kind else { panic!("not a class: {:?}", statements[0]) };
+    let StmtKind::Expression { expr, .. } = &methods[0].body[0].kind else { panic!("not an expression: {:?}", methods[0].body[0]) };
+    match &expr.kind {
+        ExprKind::Call { callee, .. } => match &callee.kind {
+            ExprKind::Super { keyword, method } => assert_eq!((keyword.span(), method.lexeme()), (Span::new(1, 24, 5), "init")),
+            kind => panic!("callee isn't super: {:?}", kind),
+        },
+        kind => panic!("not a call: {:?}", kind),
+    }
+}
+
+#[test]
+fn super_needs_a_dot_and_a_method_name() {
+    assert_eq!(parse_errors("class B < A { m() { super; } }"), ["1:25 Expected '.' after 'super'"]);
+    assert_eq!(parse_errors("class B < A { m() { super.; } }"), ["1:26 Expected superclass method name"]);
+}
+
+#[test]
+fn lambda_assigned_to_a_variable() {
+    assert_eq!(trees("var f = fun (a, b) { return a + b; };"), ["(var f (fun (a b) (block (return (+ a b)))))"]);
+}
+
+#[test]
+fn lambda_as_an_argument() {
+    assert_eq!(tree("map(list, fun (x) { return x * 2; })"), "(call map list (fun (x) (block (return (* x 2)))))");
+}
+
+#[test]
+fn fun_and_a_name_declares_while_fun_and_a_paren_is_an_expression() {
+    assert!(matches!(parse("fun name(a) {}").0[0].kind, StmtKind::Function(_)));
+    assert_eq!(trees("fun (a) { print a; };"), ["(expr (fun (a) (block (print a))))"]);
+    assert_eq!(parse_errors("fun (a) { print a; }"), ["1:20 Expected ';' after expression at end of input"]);
+}
+
+#[test]
+fn break_in_nested_loops() {
+    assert_eq!(trees("while (a) { while (b) break; break; }"), ["(while a (block (while b (break)) (break)))"]);
+}
+
+#[test]
+fn break_and_continue_outside_a_loop() {
+    assert_eq!(parse_errors("break;"), ["1:0 Must be inside a loop to use 'break'"]);
+    assert_eq!(parse_errors("fun f() { continue; }"), ["1:10 Must be inside a loop to use 'continue'"]);
+}
+
+#[test]
+fn continue_in_a_for_keeps_the_increment() {
+    let (statements, errors) = parse("for (var i = 0; i < 3; i = i + 1) { if (i == 1) continue; print i; }");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let StmtKind::Block(block) = &statements[0].kind else { panic!("not a block: {:?}", statements[0]) };
+    match &block[1].kind {
+        StmtKind::While { increment: Some(increment), body, .. } => {
+            assert_eq!(print(increment), "(= i (+ i 1))");
+            assert_eq!(print_stmt(body), "(block (if (== i 1) (continue)) (print i))");
+        }
+        kind => panic!("not a while with an increment: {:?}", kind),
+    }
+}
+
+#[test]
+fn array_literals() {
+    assert_eq!(tree("[1, 2, 3,]"), "(array 1 2 3)");
+    assert_eq!(tree("[[1],[2]]"), "(array (array 1) (array 2))");
+    assert_eq!(tree("[]"), "(array)");
+}
+
+#[test]
+fn index_chains_with_gets_and_calls() {
+    assert_eq!(tree("m[0].f()[1]"), "(index (call (. (index m 0) f)) 1)");
+}
+
+#[test]
+fn assigning_to_an_index_is_an_index_set() {
+    let expr = parse_expr("a[0] = x");
+    assert_eq!(print(&expr), "(= (index a 0) x)");
+    assert!(matches!(expr.kind, ExprKind::IndexSet { .. }), "{:?}", expr);
+}
+
+#[test]
+fn missing_closing_bracket() {
+    assert_eq!(parse_errors("a[1;"), ["1:3 Expected ']' after index"]);
+    assert_eq!(parse_errors("[1, 2;"), ["1:5 Expected ']' after array elements"]);
+}
+
+#[test]
+fn empty_index() {
+    assert_eq!(parse_errors("a[];"), ["1:2 Expected index expression"]);
+}